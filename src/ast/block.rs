@@ -25,3 +25,9 @@ impl Block {
         self
     }
 }
+
+impl Default for Block {
+    fn default() -> Self {
+        Self::new()
+    }
+}
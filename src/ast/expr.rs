@@ -1,5 +1,3 @@
-use std::fmt::Display;
-
 use crate::ast::Lit;
 use crate::span::Span;
 
@@ -46,7 +44,7 @@ impl BinExp {
     pub fn new(lhs: Expr, op: Op, rhs: Expr) -> Self {
         BinExp {
             lhs: Box::new(lhs),
-            op: Op::Add,
+            op,
             rhs: Box::new(rhs),
             span: Span::new(),
         }
@@ -74,6 +72,12 @@ impl Unit {
     }
 }
 
+impl Default for Unit {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 #[derive(Debug)]
 pub struct FnCall {
     pub path: Path,
@@ -105,3 +109,9 @@ impl FnCall {
         self
     }
 }
+
+impl Default for FnCall {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -25,3 +25,9 @@ impl File {
         self
     }
 }
+
+impl Default for File {
+    fn default() -> Self {
+        Self::new()
+    }
+}
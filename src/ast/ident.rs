@@ -1,6 +1,10 @@
+use std::fmt::Display;
+
+use serde::{Deserialize, Serialize};
+
 use crate::span::Span;
 
-#[derive(Clone, PartialEq, Debug)]
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
 pub struct Ident {
     pub raw: String,
     pub span: Span,
@@ -29,8 +33,14 @@ impl Ident {
     }
 }
 
-impl ToString for Ident {
-    fn to_string(&self) -> String {
-        self.raw.clone()
+impl Default for Ident {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Display for Ident {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.raw)
     }
 }
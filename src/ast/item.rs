@@ -1,41 +1,76 @@
+use serde::{Deserialize, Serialize};
+
 use crate::{
-    ast::{block::Block, file::File, ident::Ident},
+    ast::{block::Block, file::File, ident::Ident, path::Path},
     span::Span,
 };
 
-use super::{origin, Origin};
+use super::Origin;
 
+// Every variant is named after the struct it holds
+#[allow(clippy::enum_variant_names)]
 #[derive(Debug)]
 pub enum Item {
     ItemFn(ItemFn),
     ItemMod(ItemMod),
+    ItemUse(ItemUse),
+}
+
+/// Where an item can be used from.
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+pub enum Visibility {
+    /// Only usable from the module the item is declared in, and the modules inside of it.
+    Private,
+    /// Usable from anywhere in the same crate, declared with `pub(crate)`.
+    Crate,
+    /// Usable from anywhere, declared with `pub`.
+    Public,
 }
 
 #[derive(Debug)]
 pub struct ItemFn {
+    pub visibility: Visibility,
     pub ident: Ident,
+    /// The origin variables declared with `<'a, 'b>` after the function's name.
+    pub origin_params: Vec<Ident>,
     pub params: Vec<FnParam>,
     pub body: Block,
     pub ret_origin: Origin,
+    /// Whether the parser had to recover from an error in the body, which is left empty so that
+    /// only the signature is checked.
+    pub recovered: bool,
     pub span: Span,
 }
 
 impl ItemFn {
     pub fn new() -> Self {
         ItemFn {
+            visibility: Visibility::Private,
             ident: Ident::new(),
+            origin_params: vec![],
             params: vec![],
             body: Block::new(),
             ret_origin: Origin::Universal,
+            recovered: false,
             span: Span::new(),
         }
     }
 
+    pub fn with_visibility(mut self, visibility: Visibility) -> Self {
+        self.visibility = visibility;
+        self
+    }
+
     pub fn with_ident(mut self, ident: Ident) -> Self {
         self.ident = ident;
         self
     }
 
+    pub fn with_origin_params(mut self, origin_params: Vec<Ident>) -> Self {
+        self.origin_params = origin_params;
+        self
+    }
+
     pub fn with_params(mut self, params: Vec<FnParam>) -> Self {
         self.params = params;
         self
@@ -51,12 +86,23 @@ impl ItemFn {
         self
     }
 
+    pub fn with_recovered(mut self, recovered: bool) -> Self {
+        self.recovered = recovered;
+        self
+    }
+
     pub fn with_span(mut self, span: Span) -> Self {
         self.span = span;
         self
     }
 }
 
+impl Default for ItemFn {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 #[derive(Debug)]
 pub struct FnParam {
     pub ident: Ident,
@@ -89,22 +135,39 @@ impl FnParam {
     }
 }
 
+impl Default for FnParam {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 #[derive(Debug)]
 pub struct ItemMod {
+    pub visibility: Visibility,
     pub ident: Ident,
     pub file: File,
+    /// Whether the module's body was written inline, rather than declared with `mod name;` and
+    /// loaded from its own file.
+    pub inline: bool,
     pub span: Span,
 }
 
 impl ItemMod {
     pub fn new() -> Self {
         ItemMod {
+            visibility: Visibility::Private,
             ident: Ident::new(),
             file: File::new(),
+            inline: true,
             span: Span::new(),
         }
     }
 
+    pub fn with_visibility(mut self, visibility: Visibility) -> Self {
+        self.visibility = visibility;
+        self
+    }
+
     pub fn with_ident(mut self, ident: Ident) -> Self {
         self.ident = ident;
         self
@@ -115,8 +178,97 @@ impl ItemMod {
         self
     }
 
+    pub fn with_inline(mut self, inline: bool) -> Self {
+        self.inline = inline;
+        self
+    }
+
     pub fn with_span(mut self, span: Span) -> Self {
         self.span = span;
         self
     }
 }
+
+impl Default for ItemMod {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Debug)]
+pub struct ItemUse {
+    pub tree: UseTree,
+    pub span: Span,
+}
+
+impl ItemUse {
+    pub fn new() -> Self {
+        ItemUse {
+            tree: UseTree::new(),
+            span: Span::new(),
+        }
+    }
+
+    pub fn with_tree(mut self, tree: UseTree) -> Self {
+        self.tree = tree;
+        self
+    }
+
+    pub fn with_span(mut self, span: Span) -> Self {
+        self.span = span;
+        self
+    }
+}
+
+impl Default for ItemUse {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The path being imported by a `use` declaration, like `a::b as c` or `a::{b, c}`.
+#[derive(Debug)]
+pub struct UseTree {
+    pub prefix: Path,
+    pub kind: UseTreeKind,
+    pub span: Span,
+}
+
+#[derive(Debug)]
+pub enum UseTreeKind {
+    /// Imports the prefix itself, optionally under another name.
+    Simple(Option<Ident>),
+    /// Imports every tree in the group, relative to the prefix.
+    Nested(Vec<UseTree>),
+}
+
+impl UseTree {
+    pub fn new() -> Self {
+        UseTree {
+            prefix: Path::new(),
+            kind: UseTreeKind::Simple(None),
+            span: Span::new(),
+        }
+    }
+
+    pub fn with_prefix(mut self, prefix: Path) -> Self {
+        self.prefix = prefix;
+        self
+    }
+
+    pub fn with_kind(mut self, kind: UseTreeKind) -> Self {
+        self.kind = kind;
+        self
+    }
+
+    pub fn with_span(mut self, span: Span) -> Self {
+        self.span = span;
+        self
+    }
+}
+
+impl Default for UseTree {
+    fn default() -> Self {
+        Self::new()
+    }
+}
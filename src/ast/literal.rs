@@ -45,3 +45,9 @@ impl NumLit {
         self.value
     }
 }
+
+impl Default for NumLit {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -1,26 +1,72 @@
 use std::fmt::{Debug, Display};
 
-use crate::ast::path::Path;
+use serde::{Deserialize, Serialize};
 
-#[derive(Clone)]
+use crate::{
+    ast::{ident::Ident, path::Path},
+    lattice,
+};
+
+#[derive(Clone, Serialize, Deserialize)]
 pub enum Origin {
     Universal,
     Exact(Path),
+    /// Any of several paths, written as `{a | b}`. Always has at least two.
+    Union(Vec<Path>),
+    /// An origin variable like `'o`, which stands for whatever origin is passed in when the
+    /// function declaring it is called.
+    Variable(Ident),
+    /// Any origin except the given paths, written as `{!a}` or `{!(a | b)}`. Always has at
+    /// least one.
+    Exclude(Vec<Path>),
+    /// An origin that's inferred from the function's body, written as `{_}`. Only return values
+    /// can have one.
+    Inferred,
 }
 
 impl Origin {
-    pub fn satisfies(&self, target: &Origin) -> bool {
+    /// Every path this origin accepts values from. `*`, origin variables, exclusions and
+    /// inferred origins have none.
+    pub fn paths(&self) -> &[Path] {
         match self {
-            Self::Universal => target == &Origin::Universal,
-            Self::Exact(p_self) => match target {
-                Origin::Universal => true,
-                Origin::Exact(p_target) => p_self
-                    .to_string()
-                    .as_str()
-                    .starts_with(p_target.to_string().as_str()),
-            },
+            Self::Universal | Self::Variable(_) | Self::Exclude(_) | Self::Inferred => &[],
+            Self::Exact(path) => std::slice::from_ref(path),
+            Self::Union(paths) => paths,
+        }
+    }
+
+    /// Every path this origin refuses values from, which only exclusions have.
+    pub fn excluded(&self) -> &[Path] {
+        match self {
+            Self::Exclude(paths) => paths,
+            _ => &[],
         }
     }
+
+    /// Every path written in this origin, whether it's accepted or excluded.
+    pub fn paths_mut(&mut self) -> &mut [Path] {
+        match self {
+            Self::Universal | Self::Variable(_) | Self::Inferred => &mut [],
+            Self::Exact(path) => std::slice::from_mut(path),
+            Self::Union(paths) | Self::Exclude(paths) => paths,
+        }
+    }
+
+    /// Whether a value with this origin can be used where `target` is required. See
+    /// [`lattice`] for how origins are ordered.
+    pub fn satisfies(&self, target: &Origin) -> bool {
+        lattice::le(self, target)
+    }
+
+    /// The most specific origin that both `self` and `other` can be used as.
+    pub fn join(&self, other: &Origin) -> Origin {
+        lattice::join(self, other)
+    }
+
+    /// The least specific origin that can be used as both `self` and `other`, if there is one.
+    pub fn meet(&self, other: &Origin) -> Option<Origin> {
+        lattice::meet(self, other)
+    }
 }
 
 impl Display for Origin {
@@ -28,30 +74,31 @@ impl Display for Origin {
         match self {
             Self::Universal => write!(f, "*"),
             Self::Exact(path) => write!(f, "{}", path),
+            Self::Union(paths) => {
+                let paths = paths.iter().map(|p| p.to_string()).collect::<Vec<_>>();
+                write!(f, "{}", paths.join(" | "))
+            }
+            Self::Variable(ident) => write!(f, "'{}", ident.to_str()),
+            Self::Exclude(paths) => match paths.as_slice() {
+                [path] => write!(f, "!{}", path),
+                _ => {
+                    let paths = paths.iter().map(|p| p.to_string()).collect::<Vec<_>>();
+                    write!(f, "!({})", paths.join(" | "))
+                }
+            },
+            Self::Inferred => write!(f, "_"),
         }
     }
 }
 
 impl Debug for Origin {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self {
-            Self::Universal => write!(f, "*"),
-            Self::Exact(path) => write!(f, "{}", path),
-        }
+        write!(f, "{}", self)
     }
 }
 
 impl PartialEq for Origin {
     fn eq(&self, other: &Self) -> bool {
-        match self {
-            Origin::Universal => match other {
-                Origin::Universal => true,
-                Origin::Exact(_) => false,
-            },
-            Origin::Exact(p1) => match other {
-                Origin::Universal => false,
-                Origin::Exact(p2) => p1.to_string() == p2.to_string(),
-            },
-        }
+        lattice::eq(self, other)
     }
 }
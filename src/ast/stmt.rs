@@ -12,6 +12,16 @@ pub enum Stmt {
     Claim(Claim),
 }
 
+impl Stmt {
+    pub fn span(&self) -> Span {
+        match self {
+            Self::Local(local) => local.span.clone(),
+            Self::Return(ret) => ret.span.clone(),
+            Self::Claim(claim) => claim.span.clone(),
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct Local {
     pub ident: Ident,
@@ -44,6 +54,12 @@ impl Local {
     }
 }
 
+impl Default for Local {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 #[derive(Debug)]
 pub struct Return {
     pub expr: Expr,
@@ -69,6 +85,12 @@ impl Return {
     }
 }
 
+impl Default for Return {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 #[derive(Debug)]
 pub struct Claim {
     pub ident: Ident,
@@ -93,3 +115,9 @@ impl Claim {
         self
     }
 }
+
+impl Default for Claim {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,225 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::{
+    ast::Origin,
+    diagnostic::Diagnostic,
+    lockfile, normalize_path,
+    semantics::FunctionData,
+    source::{FileId, SourceMap},
+};
+
+/// The version of the format entries are stored in. Entries stored in any other format never
+/// match, even if they can still be read.
+const FORMAT: u32 = 2;
+
+/// Stores the results of analyzing each crate on disk, so crates that haven't changed since the
+/// last run don't need to be analyzed again.
+pub struct Cache {
+    dir: PathBuf,
+}
+
+/// Everything we need to skip analyzing a crate whose sources haven't changed.
+#[derive(Serialize, Deserialize)]
+pub struct CacheEntry {
+    /// A hash of the crate's sources and module files, along with the signatures of its
+    /// dependencies and the version of inlet that analyzed it.
+    pub key: String,
+    /// A hash of the crate's sources alone, so we can tell whether it needs to be parsed before
+    /// its dependencies have been analyzed.
+    pub sources: String,
+    /// The crate's source files, relative to the crate, in the order they were loaded. Its
+    /// manifest isn't included.
+    pub files: Vec<PathBuf>,
+    /// Every file that a module declared with `mod name;` could be loaded from, relative to the
+    /// crate, whether or not it existed. Creating one can change which file a module comes from.
+    pub modules: Vec<PathBuf>,
+    /// Every file that a span in this entry points into, relative to the crate. `FileId`s change
+    /// between runs, so spans store their file's position in this list instead.
+    paths: Vec<PathBuf>,
+    functions: Vec<(String, FunctionData)>,
+    /// Every diagnostic that was reported while analyzing the crate, so they can be shown again.
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl Cache {
+    pub fn new(dir: PathBuf) -> Self {
+        Cache { dir }
+    }
+
+    /// Finds the entry for a crate. Entries that can't be read are treated as missing.
+    pub fn load(&self, krate: &str) -> Option<CacheEntry> {
+        let source = fs::read_to_string(self.path(krate)).ok()?;
+        serde_json::from_str(&source).ok()
+    }
+
+    /// Stores the entry for a crate. The cache is only an optimization, so failing to write it
+    /// isn't an error.
+    pub fn store(&self, krate: &str, entry: &CacheEntry) {
+        let Ok(json) = serde_json::to_string(entry) else {
+            return;
+        };
+
+        if fs::create_dir_all(&self.dir).is_ok() {
+            let _ = fs::write(self.path(krate), json);
+        }
+    }
+
+    /// Crates that share a name are identified by their path too, which can't be a file name.
+    fn path(&self, krate: &str) -> PathBuf {
+        let name: String = krate
+            .chars()
+            .map(|c| match c {
+                'a'..='z' | 'A'..='Z' | '0'..='9' | '-' | '_' => c,
+                _ => '_',
+            })
+            .collect();
+
+        self.dir.join(format!("{}.json", name))
+    }
+}
+
+impl CacheEntry {
+    // Everything here ends up in the entry, so there's no smaller set of arguments to pass
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        path: &Path,
+        key: String,
+        sources: String,
+        files: Vec<PathBuf>,
+        modules: Vec<PathBuf>,
+        mut functions: Vec<(String, FunctionData)>,
+        mut diagnostics: Vec<Diagnostic>,
+        source_map: &SourceMap,
+    ) -> Self {
+        functions.sort_by(|a, b| a.0.cmp(&b.0));
+
+        let mut paths: Vec<PathBuf> = vec![];
+        remap(&mut functions, &mut diagnostics, &mut |file| {
+            // The crate's path depends on how it was found, like `-p app` or `-p /abs/app`
+            let file = lockfile::relative(path, source_map.path(file));
+            let index = match paths.iter().position(|p| *p == file) {
+                Some(index) => index,
+                None => {
+                    paths.push(file);
+                    paths.len() - 1
+                }
+            };
+
+            Some(FileId::from_index(index))
+        });
+
+        CacheEntry {
+            key,
+            sources,
+            files,
+            modules,
+            paths,
+            functions,
+            diagnostics,
+        }
+    }
+
+    /// A hash of every function in the crate, which changes whenever crates depending on it
+    /// need to be analyzed again.
+    pub fn signature(&self) -> String {
+        let json = serde_json::to_string(&(&self.paths, &self.functions))
+            .expect("Functions should always be serializable");
+
+        format!("sha256:{:x}", Sha256::digest(json))
+    }
+
+    /// Turns the entry back into the functions and diagnostics of the crate in `path`. Every file
+    /// they point into must already be in `source_map`.
+    pub fn restore(
+        mut self,
+        path: &Path,
+        source_map: &SourceMap,
+    ) -> (Vec<(String, FunctionData)>, Vec<Diagnostic>) {
+        let paths = self.paths;
+        remap(&mut self.functions, &mut self.diagnostics, &mut |file| {
+            source_map.find(&normalize_path(&path.join(paths.get(file.index())?)))
+        });
+
+        (self.functions, self.diagnostics)
+    }
+}
+
+/// Combines the hash of a crate's sources and the module files that exist with the signatures of
+/// its dependencies, given as their names and signatures. Entries written by other versions of
+/// inlet, or in another format, never match.
+pub fn key(sources: &str, modules: &[PathBuf], mut dependencies: Vec<(String, String)>) -> String {
+    dependencies.sort();
+
+    let mut hasher = Sha256::new();
+    hasher.update(env!("CARGO_PKG_VERSION"));
+    hasher.update([0]);
+    hasher.update(FORMAT.to_le_bytes());
+    hasher.update([0]);
+    hasher.update(sources);
+    for module in modules {
+        hasher.update([0]);
+        hasher.update(module.to_string_lossy().as_bytes());
+    }
+    hasher.update([1]);
+    for (name, signature) in dependencies {
+        hasher.update([0]);
+        hasher.update(name);
+        hasher.update([0]);
+        hasher.update(signature);
+    }
+
+    format!("sha256:{:x}", hasher.finalize())
+}
+
+/// Replaces the file of every span in `functions` and `diagnostics`. Files that `map` can't find
+/// are dropped, so their spans won't be shown.
+fn remap(
+    functions: &mut [(String, FunctionData)],
+    diagnostics: &mut [Diagnostic],
+    map: &mut impl FnMut(FileId) -> Option<FileId>,
+) {
+    let mut map = |file: &mut Option<FileId>| *file = file.and_then(&mut *map);
+
+    for (_, function) in functions {
+        map(&mut function.span.file);
+
+        for param in &mut function.params {
+            map(&mut param.span.file);
+            remap_origin(&mut param.origin, &mut map);
+        }
+
+        remap_origin(&mut function.ret_origin, &mut map);
+    }
+
+    for diagnostic in diagnostics {
+        map(&mut diagnostic.file);
+
+        if let Some(span) = &mut diagnostic.span {
+            map(&mut span.file);
+        }
+
+        for label in &mut diagnostic.labels {
+            map(&mut label.span.file);
+        }
+    }
+}
+
+fn remap_origin(origin: &mut Origin, map: &mut impl FnMut(&mut Option<FileId>)) {
+    if let Origin::Variable(ident) = origin {
+        map(&mut ident.span.file);
+    }
+
+    for path in origin.paths_mut() {
+        map(&mut path.span.file);
+
+        for segment in &mut path.segments {
+            map(&mut segment.span.file);
+        }
+    }
+}
@@ -0,0 +1,221 @@
+use std::collections::BTreeSet;
+
+use crate::source::{FileId, Location, SourceFile, SourceMap};
+
+use super::{Diagnostic, Severity};
+
+/// Spans longer than this many lines only show their first and last two lines.
+const MAX_SPAN_LINES: usize = 4;
+
+const BOLD: &str = "1";
+const RED: &str = "1;31";
+const YELLOW: &str = "1;33";
+const BLUE: &str = "1;34";
+
+/// Renders diagnostics in a human-readable format, with source snippets and underlined spans.
+pub struct Emitter<'a> {
+    source_map: &'a SourceMap,
+    color: bool,
+}
+
+/// A single underlined span inside of a source snippet.
+struct Annotation<'a> {
+    from: Location,
+    to: Location,
+    message: &'a str,
+    primary: bool,
+}
+
+impl<'a> Emitter<'a> {
+    pub fn new(source_map: &'a SourceMap) -> Self {
+        Emitter {
+            source_map,
+            color: false,
+        }
+    }
+
+    pub fn with_color(mut self, color: bool) -> Self {
+        self.color = color;
+        self
+    }
+
+    pub fn render(&self, diagnostic: &Diagnostic) -> String {
+        let mut out = String::new();
+
+        let style = match diagnostic.severity {
+            Severity::Error => RED,
+            Severity::Warning => YELLOW,
+            Severity::Note => BLUE,
+        };
+        let severity = match &diagnostic.code {
+            Some(code) => format!("{}[{}]", diagnostic.severity, code),
+            None => diagnostic.severity.to_string(),
+        };
+        out += &format!(
+            "{}: {}\n",
+            self.paint(&severity, style),
+            self.paint(&diagnostic.message, BOLD)
+        );
+
+        // Group every span by the file it points into, starting with the primary file. Spans that
+        // don't point into a file can't be shown.
+        let mut groups: Vec<(FileId, Vec<Annotation>)> = vec![];
+        let spans = diagnostic.span.iter().map(|span| (span, "", true)).chain(
+            diagnostic
+                .labels
+                .iter()
+                .map(|label| (&label.span, label.message.as_str(), false)),
+        );
+
+        for (span, message, primary) in spans {
+            let (Some(file), Some((from, to))) = (span.file, self.source_map.locations(span))
+            else {
+                continue;
+            };
+            let annotation = Annotation {
+                from,
+                to,
+                message,
+                primary,
+            };
+
+            match groups.iter_mut().find(|(f, _)| *f == file) {
+                Some((_, annotations)) => annotations.push(annotation),
+                None => groups.push((file, vec![annotation])),
+            }
+        }
+
+        // Make the gutter wide enough for every line number we're about to print
+        let width = groups
+            .iter()
+            .flat_map(|(_, annotations)| annotations.iter())
+            .map(|a| a.to.line.to_string().len())
+            .max()
+            .unwrap_or(0);
+        let pad = " ".repeat(width);
+
+        if groups.is_empty() {
+            if let Some(file) = diagnostic.file {
+                out += &format!(
+                    "{}{} {}\n",
+                    pad,
+                    self.paint("-->", BLUE),
+                    self.source_map.path(file).display()
+                );
+            }
+        }
+
+        for (i, (file, annotations)) in groups.iter().enumerate() {
+            let arrow = if i == 0 { "-->" } else { ":::" };
+            let first = &annotations[0].from;
+
+            out += &format!(
+                "{}{} {}:{}:{}\n",
+                pad,
+                self.paint(arrow, BLUE),
+                self.source_map.path(*file).display(),
+                first.line,
+                first.column
+            );
+
+            let source = self.source_map.get(*file);
+            out += &self.render_snippet(source, annotations, width, style);
+        }
+
+        for note in &diagnostic.notes {
+            out += &format!("{} {} {}\n", pad, self.paint("= note:", BOLD), note);
+        }
+
+        for help in &diagnostic.help {
+            out += &format!("{} {} {}\n", pad, self.paint("= help:", BOLD), help);
+        }
+
+        out
+    }
+
+    /// Prints every line touched by the given annotations, followed by their underlines.
+    fn render_snippet(
+        &self,
+        source: &SourceFile,
+        annotations: &[Annotation],
+        width: usize,
+        style: &str,
+    ) -> String {
+        let pad = " ".repeat(width);
+        let gutter = self.paint("|", BLUE);
+        let mut out = format!("{} {}\n", pad, gutter);
+
+        // Figure out which lines need to be shown, skipping the middle of very long spans
+        let mut shown = BTreeSet::new();
+        for annotation in annotations {
+            let (from, to) = (annotation.from.line, annotation.to.line);
+
+            if to - from < MAX_SPAN_LINES {
+                shown.extend(from..=to);
+            } else {
+                shown.extend([from, from + 1, to - 1, to]);
+            }
+        }
+
+        let mut previous: Option<usize> = None;
+        for line in shown {
+            let Some(text) = source.line(line) else {
+                continue;
+            };
+
+            if previous.is_some_and(|previous| line > previous + 1) {
+                out += &format!("{}\n", self.paint("...", BLUE));
+            }
+            previous = Some(line);
+
+            out += &format!(
+                "{} {} {}\n",
+                self.paint(&format!("{:>width$}", line), BLUE),
+                gutter,
+                text
+            );
+
+            for annotation in annotations {
+                let (from, to) = (&annotation.from, &annotation.to);
+                if line < from.line || line > to.line {
+                    continue;
+                }
+
+                // Columns are 1-based and inclusive
+                let start = if line == from.line {
+                    from.column
+                } else {
+                    text.chars().count() - text.trim_start().chars().count() + 1
+                };
+                let end = if line == to.line {
+                    to.column.max(start)
+                } else {
+                    text.chars().count().max(start)
+                };
+
+                let (marker, style) = match annotation.primary {
+                    true => ("^", style),
+                    false => ("-", BLUE),
+                };
+
+                let mut underline = " ".repeat(start - 1) + &marker.repeat(end - start + 1);
+                if line == to.line && !annotation.message.is_empty() {
+                    underline += " ";
+                    underline += annotation.message;
+                }
+
+                out += &format!("{} {} {}\n", pad, gutter, self.paint(&underline, style));
+            }
+        }
+
+        out
+    }
+
+    fn paint(&self, text: &str, style: &str) -> String {
+        if self.color {
+            format!("\x1b[{}m{}\x1b[0m", style, text)
+        } else {
+            text.to_owned()
+        }
+    }
+}
@@ -0,0 +1,101 @@
+use serde::Serialize;
+
+use crate::{
+    source::{Location, SourceMap},
+    span::Span,
+};
+
+use super::Diagnostic;
+
+#[derive(Serialize)]
+struct JsonDiagnostic<'a> {
+    file: Option<String>,
+    #[serde(rename = "crate")]
+    krate: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    member: Option<&'a str>,
+    severity: String,
+    code: Option<&'a str>,
+    analysis: Option<&'a str>,
+    message: &'a str,
+    span: Option<JsonSpan>,
+    labels: Vec<JsonLabel<'a>>,
+    notes: &'a [String],
+    help: &'a [String],
+}
+
+#[derive(Serialize)]
+struct JsonLabel<'a> {
+    file: Option<String>,
+    message: &'a str,
+    span: Option<JsonSpan>,
+}
+
+#[derive(Serialize)]
+struct JsonSpan {
+    from: JsonLocation,
+    to: JsonLocation,
+    byte_start: usize,
+    byte_end: usize,
+}
+
+#[derive(Serialize)]
+struct JsonLocation {
+    line: usize,
+    column: usize,
+}
+
+impl JsonSpan {
+    fn new(span: &Span, source_map: &SourceMap) -> Option<Self> {
+        let (from, to) = source_map.locations(span)?;
+
+        Some(JsonSpan {
+            from: JsonLocation::from(&from),
+            to: JsonLocation::from(&to),
+            byte_start: span.start,
+            byte_end: span.end,
+        })
+    }
+}
+
+impl From<&Location> for JsonLocation {
+    fn from(value: &Location) -> Self {
+        JsonLocation {
+            line: value.line,
+            column: value.column,
+        }
+    }
+}
+
+/// Renders a diagnostic as a single line of JSON, so it can be consumed by other tools.
+pub fn render_json(diagnostic: &Diagnostic, source_map: &SourceMap) -> String {
+    let path = |file| source_map.path(file).display().to_string();
+    let file = diagnostic.file.map(path);
+
+    let json = JsonDiagnostic {
+        file: file.clone(),
+        krate: diagnostic.krate.as_deref(),
+        member: diagnostic.member.as_deref(),
+        severity: diagnostic.severity.to_string(),
+        code: diagnostic.code.as_deref(),
+        analysis: diagnostic.analysis.as_deref(),
+        message: &diagnostic.message,
+        span: diagnostic
+            .span
+            .as_ref()
+            .and_then(|span| JsonSpan::new(span, source_map)),
+        labels: diagnostic
+            .labels
+            .iter()
+            .map(|label| JsonLabel {
+                file: label.span.file.map(path).or(file.clone()),
+                message: &label.message,
+                span: JsonSpan::new(&label.span, source_map),
+            })
+            .collect(),
+        notes: &diagnostic.notes,
+        help: &diagnostic.help,
+    };
+
+    serde_json::to_string(&json).expect("Diagnostics should always be serializable")
+}
@@ -0,0 +1,157 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{source::FileId, span::Span};
+
+mod emitter;
+mod json;
+mod sarif;
+
+pub use emitter::*;
+pub use json::*;
+pub use sarif::*;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Severity {
+    Error,
+    Warning,
+    Note,
+}
+
+impl std::fmt::Display for Severity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Error => write!(f, "error"),
+            Self::Warning => write!(f, "warning"),
+            Self::Note => write!(f, "note"),
+        }
+    }
+}
+
+/// A secondary span attached to a diagnostic, such as the declaration that a use conflicts with.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Label {
+    pub span: Span,
+    pub message: String,
+}
+
+impl Label {
+    pub fn new(span: Span, message: String) -> Self {
+        Label { span, message }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub message: String,
+    pub file: Option<FileId>,
+    pub span: Option<Span>,
+    pub labels: Vec<Label>,
+    pub notes: Vec<String>,
+    pub help: Vec<String>,
+    /// The crate this diagnostic was reported in.
+    pub krate: Option<String>,
+    /// The workspace member that was being analyzed when this diagnostic was reported.
+    pub member: Option<String>,
+    /// The name of the analysis (or compiler stage) that reported this diagnostic.
+    pub analysis: Option<String>,
+    pub code: Option<String>,
+    /// A stable identifier for the kind of problem this diagnostic describes.
+    pub rule: Option<String>,
+    /// The canonical path of the module this diagnostic was reported in.
+    pub module: Option<String>,
+}
+
+impl Diagnostic {
+    pub fn new(message: String) -> Self {
+        Diagnostic {
+            severity: Severity::Error,
+            message,
+            file: None,
+            span: None,
+            labels: vec![],
+            notes: vec![],
+            help: vec![],
+            krate: None,
+            member: None,
+            analysis: None,
+            code: None,
+            rule: None,
+            module: None,
+        }
+    }
+
+    pub fn with_severity(mut self, severity: Severity) -> Self {
+        self.severity = severity;
+        self
+    }
+
+    pub fn with_file(mut self, file: FileId) -> Self {
+        self.file = Some(file);
+        self
+    }
+
+    /// Points this diagnostic at `span`, along with the file it came from.
+    pub fn with_span(mut self, span: Span) -> Self {
+        if span.file.is_some() {
+            self.file = span.file;
+        }
+
+        self.span = Some(span);
+        self
+    }
+
+    pub fn with_label(mut self, label: Label) -> Self {
+        self.labels.push(label);
+        self
+    }
+
+    pub fn with_labels(mut self, labels: Vec<Label>) -> Self {
+        self.labels.extend(labels);
+        self
+    }
+
+    pub fn with_notes(mut self, notes: Vec<String>) -> Self {
+        self.notes.extend(notes);
+        self
+    }
+
+    pub fn with_help(mut self, help: String) -> Self {
+        self.help.push(help);
+        self
+    }
+
+    pub fn with_krate(mut self, krate: String) -> Self {
+        self.krate = Some(krate);
+        self
+    }
+
+    pub fn with_member(mut self, member: String) -> Self {
+        self.member = Some(member);
+        self
+    }
+
+    pub fn with_analysis(mut self, analysis: String) -> Self {
+        self.analysis = Some(analysis);
+        self
+    }
+
+    pub fn with_code(mut self, code: String) -> Self {
+        self.code = Some(code);
+        self
+    }
+
+    pub fn with_rule(mut self, rule: String) -> Self {
+        self.rule = Some(rule);
+        self
+    }
+
+    pub fn with_module(mut self, module: String) -> Self {
+        self.module = Some(module);
+        self
+    }
+
+    pub fn is_error(&self) -> bool {
+        self.severity == Severity::Error
+    }
+}
@@ -0,0 +1,234 @@
+use serde::Serialize;
+
+use crate::{semantics::SemaErrorKind, source::SourceMap, span::Span};
+
+use super::{Diagnostic, Severity};
+
+const SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+
+#[derive(Serialize)]
+struct Log<'a> {
+    #[serde(rename = "$schema")]
+    schema: &'static str,
+    version: &'static str,
+    runs: Vec<Run<'a>>,
+}
+
+#[derive(Serialize)]
+struct Run<'a> {
+    tool: Tool,
+    results: Vec<SarifResult<'a>>,
+}
+
+#[derive(Serialize)]
+struct Tool {
+    driver: Driver,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Driver {
+    name: &'static str,
+    version: &'static str,
+    rules: Vec<Rule>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Rule {
+    id: &'static str,
+    short_description: Message,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SarifResult<'a> {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    rule_id: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    rule_index: Option<usize>,
+    level: &'static str,
+    message: Message,
+    locations: Vec<SarifLocation>,
+    related_locations: Vec<SarifLocation>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    properties: Option<Properties<'a>>,
+}
+
+/// Extra information about a result that SARIF has no dedicated field for.
+#[derive(Serialize)]
+struct Properties<'a> {
+    member: &'a str,
+}
+
+#[derive(Serialize)]
+struct Message {
+    text: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SarifLocation {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    physical_location: Option<PhysicalLocation>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    logical_locations: Vec<LogicalLocation>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    message: Option<Message>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct PhysicalLocation {
+    artifact_location: ArtifactLocation,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    region: Option<Region>,
+}
+
+#[derive(Serialize)]
+struct ArtifactLocation {
+    uri: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Region {
+    start_line: usize,
+    start_column: usize,
+    end_line: usize,
+    /// SARIF end columns are exclusive, unlike our spans.
+    end_column: usize,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct LogicalLocation {
+    fully_qualified_name: String,
+    kind: &'static str,
+}
+
+/// Renders every diagnostic as a single SARIF 2.1.0 log, for static analysis dashboards.
+pub fn render_sarif(diagnostics: &[Diagnostic], source_map: &SourceMap) -> String {
+    let rules: Vec<Rule> = SemaErrorKind::ALL
+        .iter()
+        .map(|kind| Rule {
+            id: kind.id(),
+            short_description: Message {
+                text: kind.description().to_owned(),
+            },
+        })
+        .collect();
+
+    let results = diagnostics
+        .iter()
+        .map(|diagnostic| {
+            let rule_index = diagnostic
+                .rule
+                .as_ref()
+                .and_then(|id| rules.iter().position(|rule| rule.id == id));
+
+            let logical_locations = diagnostic
+                .module
+                .iter()
+                .map(|module| LogicalLocation {
+                    fully_qualified_name: module.clone(),
+                    kind: "module",
+                })
+                .collect();
+
+            let location = SarifLocation {
+                physical_location: diagnostic.file.map(|file| PhysicalLocation {
+                    artifact_location: ArtifactLocation {
+                        uri: uri(source_map.path(file)),
+                    },
+                    region: diagnostic
+                        .span
+                        .as_ref()
+                        .and_then(|span| region(span, source_map)),
+                }),
+                logical_locations,
+                message: None,
+            };
+
+            let related_locations = diagnostic
+                .labels
+                .iter()
+                .filter_map(|label| {
+                    let file = label.span.file?;
+
+                    Some(SarifLocation {
+                        physical_location: Some(PhysicalLocation {
+                            artifact_location: ArtifactLocation {
+                                uri: uri(source_map.path(file)),
+                            },
+                            region: region(&label.span, source_map),
+                        }),
+                        logical_locations: vec![],
+                        message: Some(Message {
+                            text: label.message.clone(),
+                        }),
+                    })
+                })
+                .collect();
+
+            SarifResult {
+                rule_id: diagnostic.rule.as_deref(),
+                rule_index,
+                level: match diagnostic.severity {
+                    Severity::Error => "error",
+                    Severity::Warning => "warning",
+                    Severity::Note => "note",
+                },
+                message: Message {
+                    text: diagnostic.message.clone(),
+                },
+                locations: vec![location],
+                related_locations,
+                properties: diagnostic
+                    .member
+                    .as_deref()
+                    .map(|member| Properties { member }),
+            }
+        })
+        .collect();
+
+    let log = Log {
+        schema: SCHEMA,
+        version: "2.1.0",
+        runs: vec![Run {
+            tool: Tool {
+                driver: Driver {
+                    name: "inlet",
+                    version: env!("CARGO_PKG_VERSION"),
+                    rules,
+                },
+            },
+            results,
+        }],
+    };
+
+    serde_json::to_string_pretty(&log).expect("SARIF logs should always be serializable")
+}
+
+fn uri(file: &std::path::Path) -> String {
+    let uri = file.to_string_lossy().replace('\\', "/");
+
+    // Relative paths are valid relative references, but absolute ones need a scheme
+    if file.is_absolute() {
+        format!("file://{}", uri)
+    } else {
+        uri
+    }
+}
+
+fn region(span: &Span, source_map: &SourceMap) -> Option<Region> {
+    let (from, to) = source_map.locations(span)?;
+
+    // SARIF end columns are exclusive, while ours are inclusive
+    Some(Region {
+        start_line: from.line,
+        start_column: from.column,
+        end_line: to.line,
+        end_column: to.column + 1,
+    })
+}
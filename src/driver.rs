@@ -0,0 +1,1324 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs,
+    ops::Range,
+    path::{Component, Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+
+use serde::Deserialize;
+use toml::Spanned;
+
+use crate::{
+    ast::{File, Item},
+    cache::{self, Cache, CacheEntry},
+    diagnostic::{Diagnostic, Label},
+    error::{self, DriverError},
+    lexer::Lexer,
+    lockfile::{self, LockedCrate, Lockfile},
+    parser::Parser,
+    scheduler,
+    semantics::{
+        Analysis, FunctionAnalysis, FunctionData, IdentAnalysis, ImportAnalysis, ImportData,
+        InferenceAnalysis, OriginAnalysis, ReturnAnalysis, SemaError, SemaErrorKind,
+    },
+    source::{FileId, SourceMap},
+    span::Span,
+    symbol::SymbolTable,
+};
+
+/// Everything we found while analyzing a project.
+pub struct AnalysisReport {
+    /// The directory of the project's root `Inlet.toml`.
+    pub root: PathBuf,
+    /// Diagnostics that don't belong to any one crate, like a manifest that couldn't be read.
+    /// They come before the diagnostics of every crate.
+    pub diagnostics: Vec<Diagnostic>,
+    /// Every crate in the project, with each crate after the crates it depends on.
+    pub crates: Vec<CrateReport>,
+    /// Every file that was loaded, so diagnostics can be rendered with their source.
+    pub source_map: SourceMap,
+    /// Whether something kept us from analyzing the project, like a broken `Inlet.toml`. The
+    /// crates that could be found are still listed, but weren't analyzed.
+    pub failed: bool,
+}
+
+pub struct CrateReport {
+    /// The name the crate gives itself in its `Inlet.toml`.
+    pub name: String,
+    /// The directory the crate lives in.
+    pub path: PathBuf,
+    /// The workspace member this crate was first found through, if the project is a workspace.
+    pub member: Option<String>,
+    /// The crate's manifest, followed by every source file loaded for it.
+    pub files: Vec<FileId>,
+    /// Every function of the crate whose return origin was inferred from `{_}`, keyed by its
+    /// canonical path.
+    pub inferred: Vec<(String, FunctionData)>,
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+impl AnalysisReport {
+    /// Every diagnostic in the report, in the order they should be shown.
+    pub fn all_diagnostics(&self) -> impl Iterator<Item = &Diagnostic> {
+        self.diagnostics
+            .iter()
+            .chain(self.crates.iter().flat_map(|krate| &krate.diagnostics))
+    }
+
+    /// Whether any errors (not warnings) were found.
+    pub fn has_errors(&self) -> bool {
+        self.all_diagnostics().any(Diagnostic::is_error)
+    }
+
+    /// A lockfile listing every crate in the report.
+    pub fn lockfile(&self) -> Lockfile {
+        Lockfile::new(
+            self.crates
+                .iter()
+                .map(|krate| LockedCrate {
+                    name: krate.name.clone(),
+                    path: lockfile::relative(&self.root, &krate.path),
+                    hash: crate_hash(&krate.path, &krate.files, &self.source_map),
+                })
+                .collect(),
+        )
+    }
+}
+
+/// How a project should be analyzed.
+pub struct AnalysisOptions {
+    /// Whether to store the results of analyzing each dependency in `target/inlet/`, next to the
+    /// root `Inlet.toml`, so dependencies that haven't changed can be loaded from there next time.
+    pub cache: bool,
+}
+
+impl AnalysisOptions {
+    pub fn new() -> Self {
+        AnalysisOptions { cache: false }
+    }
+
+    pub fn with_cache(mut self, cache: bool) -> Self {
+        self.cache = cache;
+        self
+    }
+}
+
+impl Default for AnalysisOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Analyzes the crate whose `Inlet.toml` is in `path`, along with everything it depends on, or
+/// every member of the workspace there. Nothing is written to disk unless `options` enables the
+/// cache.
+pub fn analyze_project(path: &Path, options: AnalysisOptions) -> AnalysisReport {
+    let root = normalize_path(path);
+    let mut ctx = ProcessContext::new();
+    if options.cache {
+        ctx = ctx.with_cache(Cache::new(root.join("target").join("inlet")));
+    }
+    process_root(&root, &mut ctx);
+
+    ctx.into_report(root)
+}
+
+#[derive(Deserialize)]
+struct Manifest {
+    /// Missing if this manifest only describes a workspace.
+    package: Option<Package>,
+    /// Sorted by name, so dependencies are always resolved in the same order.
+    #[serde(default)]
+    dependencies: BTreeMap<Spanned<String>, Dependency>,
+    workspace: Option<Workspace>,
+}
+
+#[derive(Deserialize)]
+struct Workspace {
+    /// The directories of the crates in this workspace, relative to its manifest.
+    members: Vec<Spanned<PathBuf>>,
+}
+
+#[derive(Deserialize)]
+struct Package {
+    name: Spanned<String>,
+}
+
+#[derive(Deserialize)]
+struct Dependency {
+    path: PathBuf,
+    /// The name the crate gives itself, when it's depended on under a different one.
+    package: Option<String>,
+}
+
+/// A crate in the dependency graph.
+struct CrateInfo {
+    /// The name the crate gives itself in its `Inlet.toml`.
+    name: String,
+    /// What the crate's functions and origins start with in every other crate. That's its name,
+    /// unless another crate in the graph has the same one, in which case its path is added.
+    id: String,
+    /// The span of that name.
+    span: Span,
+    /// The crate's manifest, followed by every source file loaded for it.
+    files: Vec<FileId>,
+    binary: bool,
+    /// Whether this crate was found as a dependency of another crate, rather than being one we
+    /// started from.
+    dependency: bool,
+    /// The directories of the crates this crate depends on.
+    dependencies: Vec<PathBuf>,
+    /// The name each dependency is known by in this crate, where that name is declared, and the
+    /// dependency's directory.
+    local_names: Vec<(String, Span, PathBuf)>,
+    /// The crate's own name and the names of its dependencies, mapped to the identity of the crate
+    /// they refer to. These are the only crates that can be named from every module.
+    aliases: SymbolTable<ImportData>,
+    /// The workspace member this crate was first found through, if we're processing a workspace.
+    member: Option<String>,
+    /// Every function of this crate whose return origin was inferred, keyed by its canonical path.
+    inferred: Vec<(String, FunctionData)>,
+    /// Every diagnostic reported for this crate so far.
+    diagnostics: Vec<Diagnostic>,
+}
+
+struct ProcessContext {
+    /// Every crate in the dependency graph, keyed by the directory it lives in.
+    crates: HashMap<PathBuf, CrateInfo>,
+    /// The directories of every crate in `crates`, with each crate after its dependencies.
+    order: Vec<PathBuf>,
+    /// The directories of the crates whose dependencies are currently being resolved, with the
+    /// innermost one last.
+    stack: Vec<PathBuf>,
+    /// The workspace member currently being resolved, if we're processing a workspace.
+    member: Option<String>,
+    /// Diagnostics that don't belong to any crate in the graph.
+    pending: Vec<Diagnostic>,
+    /// Where the results of analyzing each crate are stored between runs, if anywhere.
+    cache: Option<Cache>,
+    /// Every file we've loaded so far, so diagnostics can point into any of them.
+    source_map: SourceMap,
+    /// Whether something kept us from analyzing the project, like a broken `Inlet.toml`.
+    failed: bool,
+}
+
+impl ProcessContext {
+    pub fn new() -> Self {
+        ProcessContext {
+            crates: HashMap::new(),
+            order: vec![],
+            stack: vec![],
+            member: None,
+            pending: vec![],
+            cache: None,
+            source_map: SourceMap::new(),
+            failed: false,
+        }
+    }
+
+    pub fn with_cache(mut self, cache: Cache) -> Self {
+        self.cache = Some(cache);
+        self
+    }
+
+    /// Records a problem with the dependency graph, found while resolving the crate at `path`.
+    fn report(&mut self, path: &Path, krate: &str, error: SemaError) {
+        let diagnostics = diagnostics(krate, "dependency", vec![error]);
+
+        match self.crates.get_mut(path) {
+            Some(info) => info.diagnostics.extend(diagnostics),
+            None => self.pending.extend(diagnostics),
+        }
+    }
+
+    /// Records an error that keeps us from analyzing the project. It's shown before any other
+    /// diagnostics.
+    fn fail(&mut self, error: DriverError) {
+        self.failed = true;
+        self.pending.push(error.into_diagnostic());
+    }
+
+    /// Collects the diagnostics of every crate, tagged with the workspace member they belong to.
+    fn into_report(mut self, root: PathBuf) -> AnalysisReport {
+        let crates: Vec<CrateReport> = self
+            .order
+            .iter()
+            .map(|path| {
+                let info = self
+                    .crates
+                    .remove(path)
+                    .expect("every crate in order is known");
+
+                let diagnostics = info
+                    .diagnostics
+                    .into_iter()
+                    .map(|diagnostic| match &info.member {
+                        Some(member) => diagnostic.with_member(member.clone()),
+                        None => diagnostic,
+                    })
+                    .collect();
+
+                CrateReport {
+                    name: info.name,
+                    path: path.clone(),
+                    member: info.member,
+                    files: info.files,
+                    inferred: info.inferred,
+                    diagnostics,
+                }
+            })
+            .collect();
+
+        // Source files that couldn't be read are only found while analyzing
+        let failed = self.failed
+            || crates
+                .iter()
+                .flat_map(|krate| &krate.diagnostics)
+                .any(|diagnostic| diagnostic.analysis.as_deref() == Some(error::DRIVER));
+
+        AnalysisReport {
+            root,
+            diagnostics: self.pending,
+            crates,
+            source_map: self.source_map,
+            failed,
+        }
+    }
+}
+
+/// Turns every semantic error (or warning) that `analysis` found into a diagnostic.
+fn diagnostics(krate: &str, analysis: &str, errors: Vec<SemaError>) -> Vec<Diagnostic> {
+    errors
+        .into_iter()
+        .map(|error| {
+            error
+                .into_diagnostic()
+                .with_krate(krate.to_owned())
+                .with_analysis(analysis.to_owned())
+        })
+        .collect()
+}
+
+/// Reads the `Inlet.toml` in `path`, adding it to the source map so diagnostics can point into it.
+/// `origin` points to whatever led us to this manifest, if anything did.
+fn read_manifest(
+    path: &Path,
+    origin: Option<Span>,
+    ctx: &mut ProcessContext,
+) -> Result<(FileId, Manifest), DriverError> {
+    let manifest = fs::read_to_string(path.join("Inlet.toml")).map_err(|reason| {
+        DriverError::ManifestUnreadable {
+            path: path.to_path_buf(),
+            reason,
+            span: origin,
+        }
+    })?;
+
+    let file = ctx
+        .source_map
+        .add(path.join("Inlet.toml"), manifest.clone());
+    let manifest =
+        toml::from_str(&manifest).map_err(|err: toml::de::Error| DriverError::InvalidManifest {
+            file,
+            message: err.message().to_owned(),
+            span: err.span().map(|range| manifest_span(file, range)),
+        })?;
+
+    Ok((file, manifest))
+}
+
+/// Processes the crate at `path`, or every member of the workspace there.
+fn process_root(path: &Path, ctx: &mut ProcessContext) {
+    let path = &normalize_path(path);
+    let (file, manifest) = match read_manifest(path, None, ctx) {
+        Ok(manifest) => manifest,
+        Err(error) => {
+            return ctx.fail(error);
+        }
+    };
+
+    let result = match manifest.workspace {
+        Some(workspace) => {
+            // Libraries shared between members belong to the first member that needs them, so
+            // their diagnostics show up under that member
+            for member in &workspace.members {
+                let member_path = normalize_path(&path.join(member.get_ref()));
+
+                // Members that another member depends on were already resolved, but they're still
+                // crates we start from, so they're never loaded from the cache
+                if let Some(info) = ctx.crates.get_mut(&member_path) {
+                    info.dependency = false;
+                    continue;
+                }
+
+                ctx.member = Some(member.get_ref().display().to_string());
+                let span = manifest_span(file, member.span());
+                if let Err(error) = resolve_crate(&member_path, Some(span), ctx) {
+                    ctx.fail(error);
+                }
+            }
+
+            ctx.member = None;
+
+            // A workspace's own manifest can describe a crate too
+            if manifest.package.is_some() && !ctx.crates.contains_key(path) {
+                resolve_crate(path, None, ctx)
+            } else {
+                Ok(())
+            }
+        }
+
+        None => resolve_crate(path, None, ctx),
+    };
+
+    if let Err(error) = result {
+        ctx.fail(error);
+    }
+
+    // Crates that depend on a broken crate would only report errors caused by it
+    if !ctx.failed {
+        assign_ids(path, ctx);
+        analyze_crates(ctx);
+    }
+}
+
+/// Gives every crate in the dependency graph its identity, and tells every crate which names
+/// refer to which identities. Crates are usually identified by their name, but different crates
+/// can share one, like two versions of a vendored crate, so those add their path relative to
+/// `root` to tell them apart.
+fn assign_ids(root: &Path, ctx: &mut ProcessContext) {
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    for info in ctx.crates.values() {
+        *counts.entry(info.name.clone()).or_default() += 1;
+    }
+
+    for (path, info) in &mut ctx.crates {
+        if counts[&info.name] > 1 {
+            let relative = lockfile::relative(root, path)
+                .components()
+                .map(|component| component.as_os_str().to_string_lossy().into_owned())
+                .collect::<Vec<_>>()
+                .join("/");
+            info.id = format!("{}@{}", info.name, relative);
+        }
+    }
+
+    let ids: HashMap<PathBuf, String> = ctx
+        .crates
+        .iter()
+        .map(|(path, info)| (path.clone(), info.id.clone()))
+        .collect();
+
+    // Only the names a crate gives itself and its dependencies can start a path in it
+    for info in ctx.crates.values_mut() {
+        info.aliases.insert(
+            info.name.clone(),
+            ImportData {
+                path: info.id.clone(),
+                span: info.span.clone(),
+            },
+        );
+
+        for (name, span, dep) in &info.local_names {
+            info.aliases.insert(
+                name.clone(),
+                ImportData {
+                    path: ids[dep].clone(),
+                    span: span.clone(),
+                },
+            );
+        }
+    }
+}
+
+/// Reads the manifest of the crate at `path`, and then the manifests of everything it depends on,
+/// adding them all to the dependency graph. `origin` points to whatever led us to this crate,
+/// if anything did.
+fn resolve_crate(
+    path: &Path,
+    origin: Option<Span>,
+    ctx: &mut ProcessContext,
+) -> Result<(), DriverError> {
+    let path = &normalize_path(path);
+
+    let (manifest_file, manifest) = read_manifest(path, origin.clone(), ctx)?;
+    let package = manifest.package.ok_or(DriverError::MissingPackage {
+        file: manifest_file,
+        span: origin.clone(),
+    })?;
+
+    let krate = package.name.get_ref().clone();
+    let name_span = manifest_span(manifest_file, package.name.span());
+
+    // Next, let's figure out whether this is a binary or library crate
+    let binary = path.join("main.inlet").is_file();
+    let library = path.join("lib.inlet").is_file();
+
+    if !binary && !library {
+        return Err(DriverError::MissingEntrypoint {
+            krate,
+            path: path.clone(),
+            span: name_span,
+        });
+    }
+
+    // Only the crates we start from can be binaries, everything else is a dependency
+    if let (Some(span), true) = (origin.filter(|_| !ctx.stack.is_empty()), binary) {
+        return Err(DriverError::BinaryDependency {
+            krate,
+            span,
+            declared: name_span,
+        });
+    }
+
+    ctx.crates.insert(
+        path.clone(),
+        CrateInfo {
+            name: krate.clone(),
+            id: krate.clone(),
+            span: name_span,
+            files: vec![manifest_file],
+            binary,
+            dependency: !ctx.stack.is_empty(),
+            dependencies: vec![],
+            local_names: vec![],
+            aliases: SymbolTable::new(),
+            member: ctx.member.clone(),
+            inferred: vec![],
+            diagnostics: vec![],
+        },
+    );
+
+    ctx.stack.push(path.clone());
+
+    for (dep_name, dep) in &manifest.dependencies {
+        let dep_path = normalize_path(&path.join(&dep.path));
+        let dep_span = manifest_span(manifest_file, dep_name.span());
+
+        // Paths starting with the crate's own name couldn't tell the two crates apart
+        if dep_name.get_ref() == &krate {
+            let declared = ctx.crates[path].span.clone();
+            let error = SemaError::new(SemaErrorKind::DuplicatePackage)
+                .with_message(format!(
+                    "Crate '{}' depends on another crate under its own name",
+                    krate
+                ))
+                .with_span(dep_span)
+                .with_label(Label::new(
+                    declared,
+                    format!("the crate is named '{}' here", krate),
+                ))
+                .with_help(format!(
+                    "rename the dependency, and add `package = \"{}\"` to keep its name",
+                    krate
+                ));
+
+            // Every call into either crate would be ambiguous, so there's nothing left to analyze
+            ctx.report(path, &krate, error);
+            ctx.failed = true;
+            continue;
+        }
+
+        // A crate we're still in the middle of resolving depends on itself, so stop here rather
+        // than recursing forever
+        if let Some(start) = ctx.stack.iter().position(|p| *p == dep_path) {
+            let cycle = ctx.stack[start..]
+                .iter()
+                .chain(std::iter::once(&dep_path))
+                .map(|p| format!("'{}'", ctx.crates[p].name))
+                .collect::<Vec<_>>()
+                .join(" -> ");
+            let error = SemaError::new(SemaErrorKind::DependencyCycle)
+                .with_message(format!(
+                    "Crate '{}' depends on itself through {}",
+                    ctx.crates[&dep_path].name, cycle
+                ))
+                .with_span(dep_span)
+                .with_help(
+                    "crates can't depend on each other, so remove one of these dependencies"
+                        .to_owned(),
+                );
+
+            ctx.report(path, &krate, error);
+            continue;
+        }
+
+        // Crates that other crates depend on too only need to be resolved once, but a crate we
+        // started from might turn out to be a binary that can't be depended on
+        match ctx.crates.get(&dep_path) {
+            Some(info) if info.binary => {
+                let error = DriverError::BinaryDependency {
+                    krate: info.name.clone(),
+                    span: dep_span.clone(),
+                    declared: info.span.clone(),
+                };
+                ctx.fail(error);
+                continue;
+            }
+            Some(_) => {}
+            None => {
+                if let Err(error) = resolve_crate(&dep_path, Some(dep_span.clone()), ctx) {
+                    ctx.fail(error);
+                    continue;
+                }
+            }
+        }
+
+        // A dependency can be renamed here, but it still has to be the crate it claims to be
+        let Some(dep_info) = ctx.crates.get(&dep_path) else {
+            continue;
+        };
+
+        let name = dep_info.name.clone();
+        let expected = dep.package.as_ref().unwrap_or(dep_name.get_ref());
+
+        if name != *expected {
+            let error = SemaError::new(SemaErrorKind::DependencyNameMismatch)
+                .with_message(format!(
+                    "Dependency '{}' of crate '{}' is named '{}' in its own `Inlet.toml`",
+                    expected, krate, name
+                ))
+                .with_span(dep_span.clone())
+                .with_label(Label::new(
+                    dep_info.span.clone(),
+                    format!("the crate is named '{}' here", name),
+                ))
+                .with_help(format!(
+                    "rename the dependency to '{}', or add `package = \"{}\"` to keep its name",
+                    name, name
+                ));
+
+            ctx.report(path, &krate, error);
+        }
+
+        let info = ctx.crates.get_mut(path).expect("crate was added above");
+        info.dependencies.push(dep_path.clone());
+        info.local_names
+            .push((dep_name.get_ref().clone(), dep_span, dep_path));
+    }
+
+    ctx.stack.pop();
+    ctx.order.push(path.clone());
+
+    Ok(())
+}
+
+/// A crate that's ready to be analyzed once its dependencies have been.
+enum Prepared {
+    Parsed(ParsedCrate),
+    /// The crate's sources haven't changed since it was cached, so it wasn't parsed. It can only
+    /// be loaded from the cache if its dependencies haven't changed either.
+    Cached(CacheEntry, Vec<String>),
+}
+
+/// The result of analyzing a crate, which the crates depending on it can share.
+struct AnalyzedCrate {
+    name: String,
+    /// The crate's functions, followed by the functions of every crate it depends on.
+    functions: Arc<SymbolTable<FunctionData>>,
+    /// Every function of the crate whose return origin was inferred, keyed by its canonical path.
+    inferred: Vec<(String, FunctionData)>,
+    /// A hash of the crate's functions, or `None` if it couldn't be cached.
+    signature: Option<String>,
+    /// Every source file loaded for the crate.
+    files: Vec<FileId>,
+    diagnostics: Vec<Diagnostic>,
+}
+
+/// Lexes, parses and analyzes every crate in the dependency graph. Crates are parsed all at once,
+/// and each crate is analyzed as soon as its dependencies have been.
+fn analyze_crates(ctx: &mut ProcessContext) {
+    let order = &ctx.order;
+    let crates = &ctx.crates;
+    let cache = ctx.cache.as_ref();
+
+    let dependencies: Vec<Vec<usize>> = order
+        .iter()
+        .map(|path| {
+            crates[path]
+                .dependencies
+                .iter()
+                .map(|dep| {
+                    order
+                        .iter()
+                        .position(|p| p == dep)
+                        .expect("dependencies come before the crates that depend on them")
+                })
+                .collect()
+        })
+        .collect();
+
+    let source_map = Mutex::new(std::mem::take(&mut ctx.source_map));
+
+    let results = scheduler::run(
+        &dependencies,
+        |node| {
+            let path = &order[node];
+            prepare_crate(path, &crates[path], cache, &source_map)
+        },
+        |node, prepared, dependencies| {
+            let path = &order[node];
+            finish_crate(
+                path,
+                &crates[path],
+                prepared,
+                dependencies,
+                cache,
+                &source_map,
+            )
+        },
+    );
+
+    ctx.source_map = source_map.into_inner().expect("no job should panic");
+
+    for (path, result) in ctx.order.iter().zip(results) {
+        let info = ctx
+            .crates
+            .get_mut(path)
+            .expect("every crate in order is known");
+        info.files.extend(&result.files);
+        info.inferred.extend(result.inferred.iter().cloned());
+        info.diagnostics.extend(result.diagnostics.iter().cloned());
+    }
+}
+
+/// Parses a crate, unless it's a dependency whose sources haven't changed since it was cached.
+fn prepare_crate(
+    path: &Path,
+    info: &CrateInfo,
+    cache: Option<&Cache>,
+    source_map: &Mutex<SourceMap>,
+) -> Prepared {
+    if let Some(cache) = cache.filter(|_| info.dependency) {
+        if let Some((entry, sources)) = cached_sources(path, info, cache, source_map) {
+            return Prepared::Cached(entry, sources);
+        }
+    }
+
+    Prepared::Parsed(CrateLoader::new(&info.id, source_map).load(path, info.binary))
+}
+
+/// Analyzes a crate once every crate it depends on has been analyzed, or loads it from the cache
+/// if none of them have changed.
+fn finish_crate(
+    path: &Path,
+    info: &CrateInfo,
+    prepared: Prepared,
+    dependencies: Vec<Arc<AnalyzedCrate>>,
+    cache: Option<&Cache>,
+    source_map: &Mutex<SourceMap>,
+) -> AnalyzedCrate {
+    // A crate can only be cached if all of its dependencies could be
+    let signatures = dependencies
+        .iter()
+        .map(|dep| Some((dep.name.clone(), dep.signature.clone()?)))
+        .collect::<Option<Vec<_>>>();
+
+    let parsed = match prepared {
+        Prepared::Parsed(parsed) => parsed,
+        Prepared::Cached(entry, sources) => {
+            let modules = existing_modules(path, &entry.modules);
+            let key = signatures
+                .clone()
+                .map(|signatures| cache::key(&entry.sources, &modules, signatures));
+
+            if key.as_ref() == Some(&entry.key) {
+                return restore_crate(path, info, entry, sources, &dependencies, source_map);
+            }
+
+            CrateLoader::new(&info.id, source_map).load(path, info.binary)
+        }
+    };
+
+    let krate = info.id.clone();
+    let ast = &parsed.ast;
+    let mut diagnostics = parsed.diagnostics.clone();
+
+    // Next, we'll perform some simple semantic analysis
+    // For starters, let's collect all function definitions and then make sure all identifiers are defined
+    let mut function_analysis = FunctionAnalysis::new(ast, &info.aliases, krate.clone());
+    let result = function_analysis.analyze();
+    let mut own = function_analysis.functions();
+    if let Err(errors) = result {
+        diagnostics.extend(self::diagnostics(&krate, FunctionAnalysis::NAME, errors));
+    }
+
+    // Functions in dependencies are shared, rather than copied into this crate's table
+    let functions = dependencies.iter().fold(own.clone(), |table, dep| {
+        table.with_previous(dep.functions.clone())
+    });
+
+    // Then, figure out what every `use` declaration refers to, so we can resolve shortened paths
+    let mut import_analysis = ImportAnalysis::new(ast, &functions, &info.aliases, krate.clone());
+    let result = import_analysis.analyze();
+    let imports = import_analysis.imports();
+    if let Err(errors) = result {
+        diagnostics.extend(self::diagnostics(&krate, ImportAnalysis::NAME, errors));
+    }
+
+    let ident_analysis = IdentAnalysis::new(ast, &functions, &imports, krate.clone()).analyze();
+    if let Err(errors) = ident_analysis {
+        diagnostics.extend(self::diagnostics(&krate, IdentAnalysis::NAME, errors));
+    }
+
+    // Make sure every function actually returns something
+    let return_analysis = ReturnAnalysis::new(ast, krate.clone()).analyze();
+    if let Err(errors) = return_analysis {
+        diagnostics.extend(self::diagnostics(&krate, ReturnAnalysis::NAME, errors));
+    }
+
+    // Fill in every return origin written as `{_}`, so calls to those functions can be checked
+    let mut inference = InferenceAnalysis::new(ast, &functions, &imports, krate.clone());
+    if let Err(errors) = inference.analyze() {
+        diagnostics.extend(self::diagnostics(&krate, InferenceAnalysis::NAME, errors));
+    }
+
+    let inferred = inference.inferred();
+    for (name, function) in &inferred {
+        own.insert(name.clone(), function.clone());
+    }
+
+    let functions = dependencies.iter().fold(own.clone(), |table, dep| {
+        table.with_previous(dep.functions.clone())
+    });
+
+    // That's out of the way! Now, let's run the origin analysis
+    let origin_analysis = OriginAnalysis::new(ast, &functions, &imports, krate.clone()).analyze();
+    if let Err(errors) = origin_analysis {
+        diagnostics.extend(self::diagnostics(&krate, OriginAnalysis::NAME, errors));
+    }
+
+    // Finally, store everything the crates depending on this one need to know about it
+    let signature = signatures.map(|signatures| {
+        let source_map = source_map.lock().expect("no job should panic");
+        let files: Vec<FileId> = info.files.iter().chain(&parsed.files).copied().collect();
+        let sources = crate_hash(path, &files, &source_map);
+        let modules: Vec<PathBuf> = parsed
+            .modules
+            .iter()
+            .map(|module| lockfile::relative(path, module))
+            .collect();
+
+        let entry = CacheEntry::new(
+            path,
+            cache::key(&sources, &existing_modules(path, &modules), signatures),
+            sources,
+            parsed
+                .files
+                .iter()
+                .map(|&file| lockfile::relative(path, source_map.path(file)))
+                .collect(),
+            modules,
+            own.iter()
+                .map(|(name, function)| (name.clone(), function.clone()))
+                .collect(),
+            diagnostics.clone(),
+            &source_map,
+        );
+        drop(source_map);
+
+        if let Some(cache) = cache {
+            cache.store(&krate, &entry);
+        }
+
+        entry.signature()
+    });
+
+    AnalyzedCrate {
+        name: krate,
+        functions: Arc::new(functions),
+        inferred,
+        signature,
+        files: parsed.files,
+        diagnostics,
+    }
+}
+
+/// Hashes a crate's manifest and source files.
+fn crate_hash(path: &Path, files: &[FileId], source_map: &SourceMap) -> String {
+    lockfile::hash(files.iter().map(|&file| {
+        let source = source_map.get(file);
+        (
+            lockfile::relative(path, &source.path),
+            source.source.as_str(),
+        )
+    }))
+}
+
+/// The files in `modules`, relative to the crate in `path`, that currently exist.
+fn existing_modules(path: &Path, modules: &[PathBuf]) -> Vec<PathBuf> {
+    modules
+        .iter()
+        .filter(|module| path.join(module).is_file())
+        .cloned()
+        .collect()
+}
+
+/// Finds a crate's cache entry, along with the contents of its source files, as long as none of
+/// them have changed since it was cached.
+fn cached_sources(
+    path: &Path,
+    info: &CrateInfo,
+    cache: &Cache,
+    source_map: &Mutex<SourceMap>,
+) -> Option<(CacheEntry, Vec<String>)> {
+    let entry = cache.load(&info.id)?;
+
+    // The entry lists the crate's files, so any new ones must be declared in one of them
+    let sources = entry
+        .files
+        .iter()
+        .map(|file| fs::read_to_string(path.join(file)).ok())
+        .collect::<Option<Vec<String>>>()?;
+
+    let source_map = source_map.lock().expect("no job should panic");
+    let manifest = source_map.get(info.files[0]);
+    let files = std::iter::once((PathBuf::from("Inlet.toml"), manifest.source.as_str())).chain(
+        entry
+            .files
+            .iter()
+            .cloned()
+            .zip(sources.iter().map(String::as_str)),
+    );
+
+    (lockfile::hash(files) == entry.sources).then_some((entry, sources))
+}
+
+/// Loads a crate's functions and diagnostics from its cache entry.
+fn restore_crate(
+    path: &Path,
+    info: &CrateInfo,
+    entry: CacheEntry,
+    sources: Vec<String>,
+    dependencies: &[Arc<AnalyzedCrate>],
+    source_map: &Mutex<SourceMap>,
+) -> AnalyzedCrate {
+    let mut source_map = source_map.lock().expect("no job should panic");
+
+    let files = entry
+        .files
+        .iter()
+        .zip(sources)
+        .map(|(file, source)| source_map.add(path.join(file), source))
+        .collect();
+
+    let signature = entry.signature();
+    let (functions, diagnostics) = entry.restore(path, &source_map);
+
+    // Functions are cached in no particular order, so put them back in the order they're declared
+    let mut inferred: Vec<(String, FunctionData)> = functions
+        .iter()
+        .filter(|(_, function)| function.inferred)
+        .cloned()
+        .collect();
+    inferred
+        .sort_by_key(|(_, function)| (function.span.file.map(FileId::index), function.span.start));
+
+    let mut own = SymbolTable::new();
+    for (name, function) in functions {
+        own.insert(name, function);
+    }
+
+    let functions = dependencies
+        .iter()
+        .fold(own, |table, dep| table.with_previous(dep.functions.clone()));
+
+    AnalyzedCrate {
+        name: info.id.clone(),
+        functions: Arc::new(functions),
+        inferred,
+        signature: Some(signature),
+        files,
+        diagnostics,
+    }
+}
+
+/// The AST of a crate, along with everything we found while lexing and parsing it.
+struct ParsedCrate {
+    ast: File,
+    /// Every source file loaded for the crate.
+    files: Vec<FileId>,
+    /// Every file a module declared with `mod name;` was looked for in.
+    modules: Vec<PathBuf>,
+    diagnostics: Vec<Diagnostic>,
+}
+
+/// Loads every source file of a crate. Several crates can be loaded at once, since they only
+/// share the source map.
+struct CrateLoader<'a> {
+    krate: &'a str,
+    source_map: &'a Mutex<SourceMap>,
+    files: Vec<FileId>,
+    modules: Vec<PathBuf>,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl<'a> CrateLoader<'a> {
+    fn new(krate: &'a str, source_map: &'a Mutex<SourceMap>) -> Self {
+        CrateLoader {
+            krate,
+            source_map,
+            files: vec![],
+            modules: vec![],
+            diagnostics: vec![],
+        }
+    }
+
+    /// Parses the crate in `path`, starting from its root file.
+    fn load(mut self, path: &Path, binary: bool) -> ParsedCrate {
+        let file = path.join(if binary { "main.inlet" } else { "lib.inlet" });
+        let source = match fs::read_to_string(&file) {
+            Ok(source) => source,
+            Err(reason) => {
+                let error = DriverError::SourceUnreadable {
+                    krate: self.krate.to_owned(),
+                    path: file,
+                    reason,
+                };
+                self.diagnostics.push(error.into_diagnostic());
+
+                return ParsedCrate {
+                    ast: File::new(),
+                    files: self.files,
+                    modules: self.modules,
+                    diagnostics: self.diagnostics,
+                };
+            }
+        };
+
+        let mut ast = self.parse_file(file, source);
+
+        // Any modules declared with `mod name;` live in their own files, so load those too
+        self.load_modules(&mut ast.items, path, self.krate);
+
+        ParsedCrate {
+            ast,
+            files: self.files,
+            modules: self.modules,
+            diagnostics: self.diagnostics,
+        }
+    }
+
+    /// Lexes and parses a single source file. Anything that couldn't be lexed or parsed is
+    /// reported and left out of the returned AST.
+    fn parse_file(&mut self, file: PathBuf, source: String) -> File {
+        let slice = source.chars().collect::<Vec<char>>();
+        let id = self
+            .source_map
+            .lock()
+            .expect("no job should panic")
+            .add(file, source);
+        self.files.push(id);
+
+        // The lexer skips over anything it can't make sense of, so we can keep going
+        let mut lexer = Lexer::new(&slice).with_file(id);
+        let (tokens, spans, errors) = lexer.lex();
+        for error in errors {
+            self.diagnostics
+                .push(error.into_diagnostic().with_krate(self.krate.to_owned()));
+        }
+
+        // Any items the parser couldn't parse are left out, and the rest are analyzed
+        let mut parser = Parser::new(&tokens, &spans);
+        let (ast, errors) = parser.parse();
+        for error in errors {
+            self.diagnostics
+                .push(error.into_diagnostic().with_krate(self.krate.to_owned()));
+        }
+
+        ast
+    }
+
+    /// Loads the body of every module declared with `mod name;` in `items`, from either
+    /// `name.inlet` or `name/mod.inlet` inside of `dir`. Nested modules are looked up in a
+    /// directory named after their parent module.
+    fn load_modules(&mut self, items: &mut [Item], dir: &Path, prefix: &str) {
+        for item in items {
+            let Item::ItemMod(module) = item else {
+                continue;
+            };
+
+            let name = module.ident.to_str().to_owned();
+            let prefix = format!("{}::{}", prefix, name);
+
+            if !module.inline {
+                let candidates = [
+                    dir.join(format!("{}.inlet", name)),
+                    dir.join(&name).join("mod.inlet"),
+                ];
+                self.modules.extend(candidates.iter().cloned());
+                let found: Vec<&PathBuf> =
+                    candidates.iter().filter(|file| file.is_file()).collect();
+
+                let error = match found[..] {
+                    [file] => match fs::read_to_string(file) {
+                        Ok(source) => {
+                            module.file = self.parse_file(file.clone(), source);
+                            None
+                        }
+                        Err(err) => Some(
+                            SemaError::new(SemaErrorKind::ModuleNotFound).with_message(format!(
+                                "Couldn't read file '{}' for module '{}': {}",
+                                file.display(),
+                                name,
+                                err
+                            )),
+                        ),
+                    },
+                    [] => Some(
+                        SemaError::new(SemaErrorKind::ModuleNotFound)
+                            .with_message(format!("Couldn't find a file for module '{}'", name))
+                            .with_help(format!(
+                                "create '{}' or '{}'",
+                                candidates[0].display(),
+                                candidates[1].display()
+                            )),
+                    ),
+                    _ => Some(
+                        SemaError::new(SemaErrorKind::AmbiguousModule)
+                            .with_message(format!(
+                                "Module '{}' could be loaded from both '{}' and '{}'",
+                                name,
+                                candidates[0].display(),
+                                candidates[1].display()
+                            ))
+                            .with_help("delete one of the two files".to_owned()),
+                    ),
+                };
+
+                if let Some(error) = error {
+                    let error = error
+                        .with_span(module.span.clone())
+                        .with_module(prefix.clone());
+                    self.diagnostics
+                        .extend(diagnostics(self.krate, "module", vec![error]));
+                    continue;
+                }
+            }
+
+            self.load_modules(&mut module.file.items, &dir.join(&name), &prefix);
+        }
+    }
+}
+
+/// Turns a byte range into `Inlet.toml` into a span.
+fn manifest_span(file: FileId, range: Range<usize>) -> Span {
+    Span::new()
+        .start(range.start)
+        .end(range.end)
+        .with_file(Some(file))
+}
+
+pub fn normalize_path(path: &Path) -> PathBuf {
+    let mut components = path.components().peekable();
+    let mut ret = if let Some(c @ Component::Prefix(..)) = components.peek().cloned() {
+        components.next();
+        PathBuf::from(c.as_os_str())
+    } else {
+        PathBuf::new()
+    };
+
+    for component in components {
+        match component {
+            Component::Prefix(..) => unreachable!(),
+            Component::RootDir => {
+                ret.push(component.as_os_str());
+            }
+            Component::CurDir => {}
+            // Relative paths can start by leaving the working directory
+            Component::ParentDir => match ret.components().next_back() {
+                Some(Component::Normal(_)) => {
+                    ret.pop();
+                }
+                Some(Component::RootDir | Component::Prefix(..)) => {}
+                _ => ret.push(".."),
+            },
+            Component::Normal(c) => {
+                ret.push(c);
+            }
+        }
+    }
+    ret
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TempProject;
+
+    /// A binary crate `app`, depending on a library crate `lib` with a warning in it.
+    fn project(name: &str) -> TempProject {
+        TempProject::new(name)
+            .with_file(
+                "app/Inlet.toml",
+                "[package]\nname = \"app\"\n\n[dependencies]\nlib = { path = \"../lib\" }\n",
+            )
+            .with_file("app/main.inlet", "fn main() -> {*} {\n    return 0;\n}\n")
+            .with_file("lib/Inlet.toml", "[package]\nname = \"lib\"\n")
+            .with_file(
+                "lib/lib.inlet",
+                "pub fn id(x: {*}) -> {*} {\n    let y = x;\n}\n",
+            )
+    }
+
+    fn cached(path: &Path) -> AnalysisReport {
+        analyze_project(path, AnalysisOptions::new().with_cache(true))
+    }
+
+    #[test]
+    fn cached_diagnostics_do_not_depend_on_how_the_path_is_spelled() {
+        let project = project("cache-spelling");
+        let absolute = project.path().join("app");
+        let relative = lockfile::relative(&std::env::current_dir().unwrap(), &absolute);
+
+        let warm = cached(&absolute);
+        assert!(absolute.join("target/inlet/lib.json").is_file());
+
+        let report = cached(&relative);
+        let warning = report.all_diagnostics().next().expect("lib has a warning");
+        let (from, _) = report
+            .source_map
+            .locations(warning.span.as_ref().expect("the warning has a span"))
+            .expect("the warning points into a loaded file");
+
+        assert_eq!(warm.all_diagnostics().count(), 1);
+        assert_eq!(warning.code.as_deref(), Some("I0006"));
+        assert!(report
+            .source_map
+            .path(warning.file.unwrap())
+            .ends_with("lib/lib.inlet"));
+        assert_eq!(from.line, 3);
+    }
+
+    #[test]
+    fn cached_crates_are_analyzed_again_when_a_dependency_changes() {
+        let project = TempProject::new("cache-invalidation")
+            .with_file(
+                "app/Inlet.toml",
+                "[package]\nname = \"app\"\n\n[dependencies]\nmid = { path = \"../mid\" }\n",
+            )
+            .with_file(
+                "app/main.inlet",
+                "fn main() -> {*} {\n    return mid::run();\n}\n",
+            )
+            .with_file(
+                "mid/Inlet.toml",
+                "[package]\nname = \"mid\"\n\n[dependencies]\nlib = { path = \"../lib\" }\n",
+            )
+            .with_file(
+                "mid/lib.inlet",
+                "pub fn run() -> {*} {\n    return lib::check(0);\n}\n",
+            )
+            .with_file("lib/Inlet.toml", "[package]\nname = \"lib\"\n")
+            .with_file(
+                "lib/lib.inlet",
+                "pub fn check(user: {*}) -> {*} {\n    return 0;\n}\n",
+            );
+        let app = project.path().join("app");
+
+        assert_eq!(codes(&cached(&app)), Vec::<&str>::new());
+        assert_eq!(codes(&cached(&app)), Vec::<&str>::new());
+
+        // `mid` hasn't changed, but the function it calls no longer accepts its values
+        project.write(
+            "lib/lib.inlet",
+            "pub fn check(user: {!mid}) -> {*} {\n    return 0;\n}\n",
+        );
+        assert_eq!(codes(&cached(&app)), ["I0003"]);
+
+        // Restoring `mid` from the cache shows the error again
+        let report = cached(&app);
+        assert_eq!(codes(&report), ["I0003"]);
+        let error = report.all_diagnostics().next().unwrap();
+        assert!(report
+            .source_map
+            .path(error.file.unwrap())
+            .ends_with("mid/lib.inlet"));
+    }
+
+    fn codes(report: &AnalysisReport) -> Vec<&str> {
+        report
+            .all_diagnostics()
+            .filter_map(|diagnostic| diagnostic.code.as_deref())
+            .collect()
+    }
+
+    #[test]
+    fn crates_with_the_same_name_can_be_depended_on_together() {
+        let project = TempProject::new("same-name")
+            .with_file(
+                "app/Inlet.toml",
+                "[package]\nname = \"app\"\n\n[dependencies]\npolicies = { path = \"../pol1\" }\npolicies_v2 = { path = \"../pol2\", package = \"policies\" }\n",
+            )
+            .with_file(
+                "app/main.inlet",
+                "fn main() -> {*} {\n    let a = policies::auth(0);\n    let b = policies_v2::check(0);\n    return 0;\n}\n",
+            )
+            .with_file("pol1/Inlet.toml", "[package]\nname = \"policies\"\n")
+            .with_file(
+                "pol1/lib.inlet",
+                "pub fn auth(user: {app}) -> {*} {\n    return 0;\n}\n",
+            )
+            .with_file("pol2/Inlet.toml", "[package]\nname = \"policies\"\n")
+            .with_file(
+                "pol2/lib.inlet",
+                "pub fn check(user: {!app}) -> {*} {\n    return 0;\n}\n",
+            );
+
+        let report = analyze_project(&project.path().join("app"), AnalysisOptions::new());
+
+        // Only the second version rejects values from `app`
+        assert!(!report.failed);
+        assert_eq!(codes(&report), ["I0003"]);
+        let error = report.all_diagnostics().next().unwrap();
+        assert!(error.message.contains("'policies@../pol2::check'"));
+    }
+
+    #[test]
+    fn renamed_dependencies_can_only_be_called_by_their_local_name() {
+        let project = TempProject::new("renamed")
+            .with_file(
+                "app/Inlet.toml",
+                "[package]\nname = \"app\"\n\n[dependencies]\nauth = { path = \"../policies\", package = \"policies\" }\n",
+            )
+            .with_file(
+                "app/main.inlet",
+                "fn main() -> {*} {\n    let a = auth::check(0);\n    let b = policies::check(0);\n    return 0;\n}\n",
+            )
+            .with_file("policies/Inlet.toml", "[package]\nname = \"policies\"\n")
+            .with_file(
+                "policies/lib.inlet",
+                "pub fn check(user: {*}) -> {*} {\n    return 0;\n}\n",
+            );
+
+        let report = analyze_project(&project.path().join("app"), AnalysisOptions::new());
+
+        assert_eq!(codes(&report), ["I0002"]);
+        let error = report.all_diagnostics().next().unwrap();
+        assert!(error.message.contains("'policies::check'"));
+    }
+
+    #[test]
+    fn depending_on_a_crate_under_its_own_name_is_only_reported_once() {
+        let project = TempProject::new("own-name")
+            .with_file(
+                "app/Inlet.toml",
+                "[package]\nname = \"app\"\n\n[dependencies]\napp = { path = \"../vendor\" }\n",
+            )
+            .with_file(
+                "app/main.inlet",
+                "fn main() -> {*} {\n    let a = app::check(0);\n    return app::check(1);\n}\n",
+            )
+            .with_file("vendor/Inlet.toml", "[package]\nname = \"app\"\n")
+            .with_file(
+                "vendor/lib.inlet",
+                "pub fn check(user: {*}) -> {*} {\n    return 0;\n}\n",
+            );
+
+        let report = analyze_project(&project.path().join("app"), AnalysisOptions::new());
+
+        assert!(report.failed);
+        assert_eq!(codes(&report), ["I0017"]);
+    }
+}
@@ -1,11 +1,41 @@
+use std::{io, path::PathBuf};
+
+use crate::{
+    diagnostic::{Diagnostic, Label},
+    source::FileId,
+    span::Span,
+};
+
+/// The rule reported for every error that comes out of the lexer or parser.
+const SYNTAX_ERROR: &str = "syntax-error";
+
+/// The analysis reported for every error that kept us from analyzing a project at all.
+pub const DRIVER: &str = "driver";
+
 #[derive(Debug)]
 pub struct LexError {
     pub reason: String,
+    pub span: Span,
 }
 
 impl LexError {
     pub fn new(reason: String) -> Self {
-        LexError { reason }
+        LexError {
+            reason,
+            span: Span::new(),
+        }
+    }
+
+    pub fn with_span(mut self, span: Span) -> Self {
+        self.span = span;
+        self
+    }
+
+    pub fn into_diagnostic(self) -> Diagnostic {
+        Diagnostic::new(self.reason)
+            .with_span(self.span)
+            .with_analysis("lexer".to_owned())
+            .with_rule(SYNTAX_ERROR.to_owned())
     }
 }
 
@@ -18,11 +48,27 @@ impl From<String> for LexError {
 #[derive(Debug)]
 pub struct ParseError {
     pub reason: String,
+    pub span: Span,
 }
 
 impl ParseError {
     pub fn new(reason: String) -> Self {
-        ParseError { reason }
+        ParseError {
+            reason,
+            span: Span::new(),
+        }
+    }
+
+    pub fn with_span(mut self, span: Span) -> Self {
+        self.span = span;
+        self
+    }
+
+    pub fn into_diagnostic(self) -> Diagnostic {
+        Diagnostic::new(self.reason)
+            .with_span(self.span)
+            .with_analysis("parser".to_owned())
+            .with_rule(SYNTAX_ERROR.to_owned())
     }
 }
 
@@ -31,3 +77,149 @@ impl From<String> for ParseError {
         ParseError::new(value)
     }
 }
+
+/// A problem with a project's manifests or files that keeps us from analyzing it, as opposed to a
+/// problem with the code being analyzed. Spans point to whatever led us to the broken crate, like
+/// the dependency that names it, when there is one.
+#[derive(Debug)]
+pub enum DriverError {
+    /// A crate's `Inlet.toml` couldn't be read.
+    ManifestUnreadable {
+        path: PathBuf,
+        reason: io::Error,
+        span: Option<Span>,
+    },
+    /// A crate's `Inlet.toml` isn't valid TOML, or is missing something we need.
+    InvalidManifest {
+        file: FileId,
+        message: String,
+        span: Option<Span>,
+    },
+    /// A manifest without a `[package]` section was used as a crate.
+    MissingPackage { file: FileId, span: Option<Span> },
+    /// A crate has neither a `main.inlet` nor a `lib.inlet`.
+    MissingEntrypoint {
+        krate: String,
+        path: PathBuf,
+        span: Span,
+    },
+    /// A binary crate was used as a dependency of another crate.
+    BinaryDependency {
+        krate: String,
+        span: Span,
+        declared: Span,
+    },
+    /// One of a crate's source files couldn't be read.
+    SourceUnreadable {
+        krate: String,
+        path: PathBuf,
+        reason: io::Error,
+    },
+}
+
+impl DriverError {
+    /// A short, kebab-case name for the kind of error, like the rules of semantic errors.
+    pub fn id(&self) -> &'static str {
+        match self {
+            Self::ManifestUnreadable { .. } => "unreadable-manifest",
+            Self::InvalidManifest { .. } => "invalid-manifest",
+            Self::MissingPackage { .. } => "missing-package",
+            Self::MissingEntrypoint { .. } => "missing-entrypoint",
+            Self::BinaryDependency { .. } => "binary-dependency",
+            Self::SourceUnreadable { .. } => "unreadable-source",
+        }
+    }
+
+    pub fn into_diagnostic(self) -> Diagnostic {
+        let rule = self.id().to_owned();
+
+        let diagnostic = match self {
+            Self::ManifestUnreadable { path, reason, span } => {
+                let diagnostic = match reason.kind() {
+                    io::ErrorKind::NotFound => Diagnostic::new(format!(
+                        "Couldn't find `Inlet.toml` in '{}'",
+                        path.display()
+                    ))
+                    .with_help("check that the path points to the root of a crate".to_owned()),
+                    _ => Diagnostic::new(format!(
+                        "Couldn't read `Inlet.toml` in '{}': {}",
+                        path.display(),
+                        reason
+                    )),
+                };
+
+                match span {
+                    Some(span) => diagnostic.with_span(span),
+                    None => diagnostic,
+                }
+            }
+            Self::InvalidManifest {
+                file,
+                message,
+                span,
+            } => {
+                let diagnostic =
+                    Diagnostic::new(format!("Couldn't parse `Inlet.toml`: {}", message.trim()))
+                        .with_file(file);
+
+                match span {
+                    Some(span) => diagnostic.with_span(span),
+                    None => diagnostic,
+                }
+            }
+            Self::MissingPackage { file, span } => {
+                let diagnostic =
+                    Diagnostic::new("`Inlet.toml` has no `[package]` section".to_owned())
+                        .with_file(file)
+                        .with_help(
+                            "add a `[package]` section with the crate's `name`".to_owned(),
+                        );
+
+                match span {
+                    Some(span) => diagnostic.with_label(Label::new(
+                        span,
+                        "used as a crate here".to_owned(),
+                    )),
+                    None => diagnostic,
+                }
+            }
+            Self::MissingEntrypoint { krate, path, span } => Diagnostic::new(format!(
+                "Crate '{}' has neither a `main.inlet` (binary entrypoint) nor a `lib.inlet` (library entrypoint)",
+                krate
+            ))
+            .with_span(span)
+            .with_help(format!(
+                "create '{}' or '{}'",
+                path.join("main.inlet").display(),
+                path.join("lib.inlet").display()
+            )),
+            Self::BinaryDependency {
+                krate,
+                span,
+                declared,
+            } => Diagnostic::new(format!(
+                "Binary crate '{}' can't be used as a dependency",
+                krate
+            ))
+            .with_span(span)
+            .with_label(Label::new(
+                declared,
+                format!("'{}' is a binary, since it has a `main.inlet`", krate),
+            ))
+            .with_help("move the functions other crates need into a library crate".to_owned()),
+            Self::SourceUnreadable {
+                krate,
+                path,
+                reason,
+            } => Diagnostic::new(format!(
+                "Couldn't read file '{}' in crate '{}': {}",
+                path.display(),
+                krate,
+                reason
+            ))
+            .with_krate(krate),
+        };
+
+        diagnostic.with_analysis(DRIVER.to_owned()).with_rule(rule)
+    }
+}
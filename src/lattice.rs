@@ -0,0 +1,422 @@
+//! Origins form a lattice, ordered by how specific they are. `{policies::product}` sits below
+//! `{policies}`, since every value claimed by the `product` module is also claimed by its crate,
+//! and `{*}` sits above every other origin. A value can be used wherever an origin at or above its
+//! own is required.
+//!
+//! A union like `{auth | billing}` sits above each of its paths. A value with a union origin could
+//! come from any of them, so it can only be used where every one of them is accepted.
+//!
+//! An origin variable like `'o` stands for an origin we don't know yet, so it's only ever below
+//! itself and `{*}`.
+//!
+//! An exclusion like `{!walkdir}` accepts values from every path that has nothing to do with
+//! `walkdir`. `{*}` isn't below it, since a value that could come from anywhere could come from
+//! `walkdir`, and neither is `{walkdir::entries}` nor `{crate}`, which might contain it. When an
+//! exclusion is combined with a path it only partly covers, like `{!auth}` and `{auth::login}`, no
+//! origin describes the result exactly, so `join` gives up and returns `{*}` and `meet` leaves the
+//! path out.
+//!
+//! An inferred origin `{_}` that hasn't been inferred yet sits below every other origin, since
+//! nothing is known to flow out of the function yet.
+//!
+//! Paths are compared segment by segment rather than as strings, so `{policiesx}` and
+//! `{policies::product::create_all}` have nothing to do with `{policies::product::create}`.
+//! Relative origins like `{self}` must be canonicalized before they're compared.
+
+use crate::ast::{Ident, Origin, Path};
+
+/// Whether `lower` is at or below `upper`, meaning a value with origin `lower` can be used where
+/// `upper` is required.
+pub fn le(lower: &Origin, upper: &Origin) -> bool {
+    match (lower, upper) {
+        (Origin::Inferred, _) => true,
+        (_, Origin::Inferred) => false,
+        (_, Origin::Universal) => true,
+        (Origin::Variable(lower), Origin::Variable(upper)) => lower.to_str() == upper.to_str(),
+        (Origin::Universal | Origin::Variable(_), _) | (_, Origin::Variable(_)) => false,
+        // Excluding more paths is more specific
+        (Origin::Exclude(lower), Origin::Exclude(upper)) => upper
+            .iter()
+            .all(|upper| lower.iter().any(|lower| path_le(upper, lower))),
+        (Origin::Exclude(_), _) => false,
+        (_, Origin::Exclude(excluded)) => lower
+            .paths()
+            .iter()
+            .all(|lower| excluded.iter().all(|excluded| disjoint(lower, excluded))),
+        _ => lower
+            .paths()
+            .iter()
+            .all(|lower| upper.paths().iter().any(|upper| path_le(lower, upper))),
+    }
+}
+
+/// Whether two origins are the same, regardless of where they were written or how a union's
+/// paths are ordered.
+pub fn eq(a: &Origin, b: &Origin) -> bool {
+    le(a, b) && le(b, a)
+}
+
+/// The most specific origin that both `a` and `b` can be used as, which is the union of their
+/// paths.
+pub fn join(a: &Origin, b: &Origin) -> Origin {
+    union([a.clone(), b.clone()])
+}
+
+/// The least specific origin that can be used as both `a` and `b`, or `None` if no value could
+/// have both origins, like `{auth}` and `{billing}`.
+pub fn meet(a: &Origin, b: &Origin) -> Option<Origin> {
+    match (a, b) {
+        (Origin::Inferred, _) | (_, Origin::Inferred) => Some(Origin::Inferred),
+        (Origin::Universal, _) => Some(b.clone()),
+        (_, Origin::Universal) => Some(a.clone()),
+        (Origin::Variable(_), _) | (_, Origin::Variable(_)) => eq(a, b).then(|| a.clone()),
+        (Origin::Exclude(a), Origin::Exclude(b)) => Some(exclude(a.iter().chain(b).cloned())),
+        (Origin::Exclude(excluded), other) | (other, Origin::Exclude(excluded)) => {
+            let paths = other
+                .paths()
+                .iter()
+                .filter(|path| excluded.iter().all(|excluded| disjoint(path, excluded)))
+                .cloned();
+
+            from_paths(paths)
+        }
+        _ => from_paths(intersect(a.paths(), b.paths())),
+    }
+}
+
+/// Combines several origins into one that accepts values from any of them. Paths that another
+/// path already covers are left out, so `{auth | auth::login}` becomes `{auth}`.
+pub fn union(origins: impl IntoIterator<Item = Origin>) -> Origin {
+    let mut paths = vec![];
+    let mut variable: Option<Ident> = None;
+    let mut excluded: Option<Vec<Path>> = None;
+    let mut inferred = false;
+
+    for origin in origins {
+        match origin {
+            Origin::Inferred => inferred = true,
+            Origin::Universal => return Origin::Universal,
+            Origin::Exact(path) => paths.push(path),
+            Origin::Union(alternatives) => paths.extend(alternatives),
+            Origin::Variable(ident) => match &variable {
+                Some(other) if other.to_str() != ident.to_str() => return Origin::Universal,
+                _ => variable = Some(ident),
+            },
+            // Only the paths that every exclusion refuses are still refused
+            Origin::Exclude(others) => {
+                excluded = Some(match excluded {
+                    Some(excluded) => intersect(&excluded, &others),
+                    None => others,
+                })
+            }
+        }
+    }
+
+    // Variables can't be part of a union, so only `*` is above a variable and anything else
+    match (variable, excluded) {
+        (Some(ident), None) if paths.is_empty() => Origin::Variable(ident),
+        (Some(_), _) => Origin::Universal,
+        (None, Some(excluded)) => {
+            // A path that's accepted can't be refused, and one that's partly accepted can't be
+            // described, so neither stays excluded
+            let excluded: Vec<Path> = excluded
+                .into_iter()
+                .filter(|excluded| paths.iter().all(|path| disjoint(path, excluded)))
+                .collect();
+
+            match excluded.is_empty() {
+                true => Origin::Universal,
+                false => exclude(excluded),
+            }
+        }
+        (None, None) => match from_paths(paths) {
+            Some(origin) => origin,
+            // Nothing is below an origin that hasn't been inferred yet
+            None if inferred => Origin::Inferred,
+            None => Origin::Universal,
+        },
+    }
+}
+
+/// Refuses values from any of `paths`. Paths that another path already covers are left out, so
+/// `{!(auth | auth::login)}` becomes `{!auth}`.
+pub fn exclude(paths: impl IntoIterator<Item = Path>) -> Origin {
+    Origin::Exclude(normalize(paths))
+}
+
+/// The more specific path of every pair from `a` and `b` that are ordered at all, which are the
+/// paths both of them cover.
+fn intersect(a: &[Path], b: &[Path]) -> Vec<Path> {
+    a.iter()
+        .flat_map(|a| b.iter().map(move |b| (a, b)))
+        .filter_map(|(a, b)| match (path_le(a, b), path_le(b, a)) {
+            (true, _) => Some(a.clone()),
+            (_, true) => Some(b.clone()),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Turns the paths that aren't covered by another one into an origin, or `None` if there are
+/// no paths at all.
+fn from_paths(paths: impl IntoIterator<Item = Path>) -> Option<Origin> {
+    let mut kept = normalize(paths);
+
+    match kept.len() {
+        0 => None,
+        1 => kept.pop().map(Origin::Exact),
+        _ => Some(Origin::Union(kept)),
+    }
+}
+
+/// Leaves out the paths that another one covers.
+fn normalize(paths: impl IntoIterator<Item = Path>) -> Vec<Path> {
+    let mut kept: Vec<Path> = vec![];
+
+    for path in paths {
+        if kept.iter().any(|other| path_le(&path, other)) {
+            continue;
+        }
+
+        kept.retain(|other| !path_le(other, &path));
+        kept.push(path);
+    }
+
+    kept
+}
+
+/// Whether every value from `lower` also comes from `upper`, because `upper` is a prefix of it.
+fn path_le(lower: &Path, upper: &Path) -> bool {
+    upper.segments.len() <= lower.segments.len()
+        && lower
+            .segments
+            .iter()
+            .zip(&upper.segments)
+            .all(|(lower, upper)| lower.to_str() == upper.to_str())
+}
+
+/// Whether no value could come from both `a` and `b`, because neither contains the other.
+fn disjoint(a: &Path, b: &Path) -> bool {
+    !path_le(a, b) && !path_le(b, a)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use proptest::prelude::*;
+
+    use super::*;
+    use crate::span::Span;
+
+    /// Segments that are prefixes of each other as strings, so string matching would be caught.
+    const SEGMENTS: [&str; 4] = ["a", "ab", "b", "policies"];
+
+    fn path(segments: &[&str], offset: usize) -> Path {
+        let segments = segments
+            .iter()
+            .map(|raw| {
+                Ident::new()
+                    .with_raw(raw.to_string())
+                    .with_span(Span::new().start(offset).end(offset + raw.len()))
+            })
+            .collect();
+
+        Path::new().with_segments(segments)
+    }
+
+    fn origin() -> impl Strategy<Value = Origin> {
+        let segment = prop::sample::select(SEGMENTS.to_vec());
+
+        let exact = (prop::collection::vec(segment, 1..4), 0..100usize)
+            .prop_map(|(segments, offset)| path(&segments, offset));
+
+        // Unions and exclusions aren't normalized, since the parser doesn't normalize them either
+        prop_oneof![
+            1 => Just(Origin::Inferred),
+            1 => Just(Origin::Universal),
+            4 => exact.clone().prop_map(Origin::Exact),
+            2 => prop::collection::vec(exact.clone(), 2..4).prop_map(Origin::Union),
+            2 => prop::collection::vec(exact, 1..3).prop_map(Origin::Exclude),
+        ]
+    }
+
+    /// Whether `join` and `meet` are exact for `a` and `b`, which they aren't when one of them
+    /// excludes a path that the other only partly covers.
+    fn precise(a: &Origin, b: &Origin) -> bool {
+        // One path strictly contains the other
+        let partial = |a: &Origin, b: &Origin| {
+            a.excluded().iter().any(|excluded| {
+                b.paths()
+                    .iter()
+                    .any(|path| path_le(path, excluded) != path_le(excluded, path))
+            })
+        };
+
+        !partial(a, b) && !partial(b, a)
+    }
+
+    /// What an origin is made of, with the paths that another one covers left out. Unlike `eq`,
+    /// this doesn't depend on `le`, so the order can be checked against it.
+    fn structure(origin: &Origin) -> (&'static str, BTreeSet<Vec<String>>) {
+        let paths = |paths: &[Path]| {
+            normalize(paths.iter().cloned())
+                .iter()
+                .map(|path| path.segments.iter().map(|s| s.to_string()).collect())
+                .collect()
+        };
+
+        match origin {
+            Origin::Inferred => ("_", BTreeSet::new()),
+            Origin::Universal => ("*", BTreeSet::new()),
+            Origin::Variable(ident) => ("'", BTreeSet::from([vec![ident.to_string()]])),
+            Origin::Exact(_) | Origin::Union(_) => ("paths", paths(origin.paths())),
+            Origin::Exclude(excluded) => ("!", paths(excluded)),
+        }
+    }
+
+    #[test]
+    fn segments_are_not_compared_as_strings() {
+        let policies = Origin::Exact(path(&["policies"], 0));
+        let policiesx = Origin::Exact(path(&["policiesx"], 0));
+        let create = Origin::Exact(path(&["policies", "product", "create"], 0));
+        let create_all = Origin::Exact(path(&["policies", "product", "create_all"], 0));
+
+        assert!(!le(&policiesx, &policies));
+        assert!(!le(&create_all, &create));
+        assert!(le(&create, &policies));
+        assert_eq!(meet(&create, &create_all), None);
+    }
+
+    #[test]
+    fn unions_accept_any_alternative() {
+        let auth = Origin::Exact(path(&["auth"], 0));
+        let login = Origin::Exact(path(&["auth", "login"], 0));
+        let billing = Origin::Exact(path(&["billing"], 0));
+        let either = Origin::Union(vec![path(&["billing"], 0), path(&["auth"], 0)]);
+
+        assert!(le(&login, &either));
+        assert!(le(&billing, &either));
+        assert!(!le(&either, &auth));
+        assert!(le(&either, &join(&auth, &billing)));
+        assert_eq!(union([auth.clone(), login]).to_string(), "auth");
+        assert_eq!(meet(&either, &auth), Some(auth));
+    }
+
+    #[test]
+    fn exclusions_refuse_overlapping_paths() {
+        let auth = Origin::Exact(path(&["auth"], 0));
+        let login = Origin::Exact(path(&["auth", "login"], 0));
+        let billing = Origin::Exact(path(&["billing"], 0));
+        let not_auth = Origin::Exclude(vec![path(&["auth"], 0)]);
+        let not_login = Origin::Exclude(vec![path(&["auth", "login"], 0)]);
+
+        assert!(le(&billing, &not_auth));
+        assert!(!le(&login, &not_auth));
+        assert!(!le(&auth, &not_login));
+        assert!(!le(&Origin::Universal, &not_auth));
+        assert!(le(&not_auth, &not_login));
+        assert!(!le(&not_login, &not_auth));
+        assert_eq!(join(&not_auth, &billing), not_auth);
+        assert_eq!(join(&not_auth, &login), Origin::Universal);
+        assert_eq!(meet(&not_login, &auth), None);
+        assert_eq!(meet(&not_auth, &not_login), Some(not_auth));
+    }
+
+    proptest! {
+        #[test]
+        fn order_is_reflexive(a in origin()) {
+            prop_assert!(le(&a, &a));
+        }
+
+        #[test]
+        fn order_is_antisymmetric(a in origin(), b in origin()) {
+            if le(&a, &b) && le(&b, &a) {
+                prop_assert_eq!(structure(&a), structure(&b));
+            }
+        }
+
+        #[test]
+        fn order_is_transitive(a in origin(), b in origin(), c in origin()) {
+            if le(&a, &b) && le(&b, &c) {
+                prop_assert!(le(&a, &c));
+            }
+        }
+
+        #[test]
+        fn universal_is_top(a in origin()) {
+            prop_assert!(le(&a, &Origin::Universal));
+        }
+
+        #[test]
+        fn equality_ignores_spans(segments in prop::collection::vec(prop::sample::select(SEGMENTS.to_vec()), 1..4)) {
+            let a = Origin::Exact(path(&segments, 0));
+            let b = Origin::Exact(path(&segments, 50));
+
+            prop_assert!(eq(&a, &b));
+            prop_assert_eq!(a, b);
+        }
+
+        #[test]
+        fn union_keeps_meaning(a in origin(), b in origin()) {
+            prop_assert!(eq(&union([a.clone()]), &a));
+            prop_assert!(eq(&union([a.clone(), b.clone()]), &join(&a, &b)));
+        }
+
+        #[test]
+        fn join_is_least_upper_bound(a in origin(), b in origin(), c in origin()) {
+            let join = join(&a, &b);
+
+            prop_assert!(le(&a, &join));
+            prop_assert!(le(&b, &join));
+
+            if precise(&a, &b) && le(&a, &c) && le(&b, &c) {
+                prop_assert!(le(&join, &c));
+            }
+        }
+
+        #[test]
+        fn meet_is_greatest_lower_bound(a in origin(), b in origin(), c in origin()) {
+            match meet(&a, &b) {
+                Some(meet) => {
+                    prop_assert!(le(&meet, &a));
+                    prop_assert!(le(&meet, &b));
+
+                    if precise(&a, &b) && le(&c, &a) && le(&c, &b) {
+                        prop_assert!(le(&c, &meet));
+                    }
+                }
+                // Only an origin that hasn't been inferred yet could be below both
+                None if precise(&a, &b) => prop_assert!(
+                    matches!(c, Origin::Inferred) || !(le(&c, &a) && le(&c, &b))
+                ),
+                None => {}
+            }
+        }
+
+        #[test]
+        fn join_is_commutative_and_idempotent(a in origin(), b in origin()) {
+            prop_assert!(eq(&join(&a, &b), &join(&b, &a)));
+            prop_assert!(eq(&join(&a, &a), &a));
+        }
+
+        #[test]
+        fn join_is_associative(a in origin(), b in origin(), c in origin()) {
+            if precise(&a, &b) && precise(&b, &c) && precise(&a, &c) {
+                prop_assert!(eq(&join(&join(&a, &b), &c), &join(&a, &join(&b, &c))));
+            }
+        }
+
+        #[test]
+        fn meet_is_commutative_and_idempotent(a in origin(), b in origin()) {
+            prop_assert_eq!(meet(&a, &b), meet(&b, &a));
+            prop_assert_eq!(meet(&a, &a), Some(a));
+        }
+
+        #[test]
+        fn join_and_meet_absorb(a in origin(), b in origin()) {
+            prop_assert!(eq(&join(&a, &meet(&a, &b).unwrap_or_else(|| a.clone())), &a));
+            prop_assert_eq!(meet(&a, &join(&a, &b)), Some(a));
+        }
+    }
+}
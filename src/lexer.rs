@@ -1,9 +1,8 @@
-use std::{fmt::format, str::Chars};
-
 use crate::{
     ast::{Ident, NumLit},
     error::LexError,
-    span::{Location, Span},
+    source::FileId,
+    span::Span,
     token::Token,
 };
 
@@ -13,10 +12,12 @@ pub struct Lexer<'a> {
     source: &'a [char],
     spans: Vec<Span>,
     index: usize,
-    line: usize,
-    column: usize,
-    start: Location,
-    end: Location,
+    /// The byte offset of `index` in the source.
+    offset: usize,
+    /// The byte offset where the current token starts.
+    start: usize,
+    /// The file being lexed, which every span will point into.
+    file: Option<FileId>,
 }
 
 impl<'a> Lexer<'a> {
@@ -25,20 +26,24 @@ impl<'a> Lexer<'a> {
             source: input,
             spans: vec![],
             index: 0,
-            line: 1,
-            column: 1,
-            start: Location::new(),
-            end: Location::new(),
+            offset: 0,
+            start: 0,
+            file: None,
         }
     }
 
-    pub fn next(&mut self) -> LexResult<Token> {
+    pub fn with_file(mut self, file: FileId) -> Self {
+        self.file = Some(file);
+        self
+    }
+
+    pub fn next_token(&mut self) -> LexResult<Token> {
         while self.index < self.source.len() && self.current().is_whitespace() {
             self.step(1);
         }
 
         if self.index >= self.source.len() {
-            return Ok(Token::EOF);
+            return Ok(Token::Eof);
         }
 
         let current = self.current();
@@ -61,6 +66,9 @@ impl<'a> Lexer<'a> {
                 "mod" => Ok(Token::KwMod),
                 "return" => Ok(Token::KwReturn),
                 "claim" => Ok(Token::KwClaim),
+                "use" => Ok(Token::KwUse),
+                "as" => Ok(Token::KwAs),
+                "pub" => Ok(Token::KwPub),
                 _ => Ok(Token::Ident(Ident::new().with_raw(raw))),
             }
         } else if current.is_numeric() {
@@ -73,16 +81,18 @@ impl<'a> Lexer<'a> {
                 self.step(1);
             }
 
-            self.complete();
             let value: String = self.source[i..self.index].iter().collect();
-            let value: i32 = value.parse().map_err(|_| {
-                LexError::from(format!("Could not convert '{}' into an `i32`", value))
-            })?;
+            let value: i32 = match value.parse() {
+                Ok(value) => value,
+                Err(_) => {
+                    return Err(self.error(format!("Could not convert '{}' into an `i32`", value)))
+                }
+            };
 
-            return Ok(Token::NumLit(NumLit::new().with_value(value)));
+            self.complete();
+            Ok(Token::NumLit(NumLit::new().with_value(value)))
         } else {
             // Must be a symbol of some kind... mark the beginning
-            let i = self.index;
             self.start();
 
             match current {
@@ -90,49 +100,99 @@ impl<'a> Lexer<'a> {
                 '+' => {
                     self.step(1);
                     self.complete();
-                    return Ok(Token::Plus);
+                    Ok(Token::Plus)
                 }
 
                 '(' => {
                     self.step(1);
                     self.complete();
-                    return Ok(Token::LParen);
+                    Ok(Token::LParen)
                 }
 
                 ')' => {
                     self.step(1);
                     self.complete();
-                    return Ok(Token::RParen);
+                    Ok(Token::RParen)
                 }
 
                 '[' => {
                     self.step(1);
                     self.complete();
-                    return Ok(Token::LBracket);
+                    Ok(Token::LBracket)
                 }
 
                 ']' => {
                     self.step(1);
                     self.complete();
-                    return Ok(Token::RBracket);
+                    Ok(Token::RBracket)
                 }
 
                 '{' => {
                     self.step(1);
                     self.complete();
-                    return Ok(Token::LBrace);
+                    Ok(Token::LBrace)
                 }
 
                 '}' => {
                     self.step(1);
                     self.complete();
-                    return Ok(Token::RBrace);
+                    Ok(Token::RBrace)
                 }
 
                 '*' => {
                     self.step(1);
                     self.complete();
-                    return Ok(Token::Star);
+                    Ok(Token::Star)
+                }
+
+                '|' => {
+                    self.step(1);
+                    self.complete();
+                    Ok(Token::Pipe)
+                }
+
+                '!' => {
+                    self.step(1);
+                    self.complete();
+                    Ok(Token::Bang)
+                }
+
+                '<' => {
+                    self.step(1);
+                    self.complete();
+                    Ok(Token::Lt)
+                }
+
+                '>' => {
+                    self.step(1);
+                    self.complete();
+                    Ok(Token::Gt)
+                }
+
+                '\'' => {
+                    self.step(1);
+                    if self.index >= self.source.len() {
+                        return Err(self.error(
+                            "Expected the name of an origin variable after ', but reached the end of the file"
+                                .to_owned(),
+                        ));
+                    }
+
+                    if !(self.current().is_alphabetic() || self.current() == '_') {
+                        return Err(self.error(format!(
+                            "Expected the name of an origin variable after ', but found '{}'",
+                            self.current()
+                        )));
+                    }
+
+                    let i = self.index;
+                    while self.current().is_alphanumeric() || self.current() == '_' {
+                        self.step(1);
+                    }
+
+                    self.complete();
+                    let raw: String = self.source[i..self.index].iter().collect();
+                    Ok(Token::OriginVar(Ident::new().with_raw(raw)))
                 }
 
                 '-' => {
@@ -140,31 +200,28 @@ impl<'a> Lexer<'a> {
                     if self.current() == '>' {
                         self.step(1);
                         self.complete();
-                        return Ok(Token::RArrow);
+                        Ok(Token::RArrow)
                     } else {
-                        return Err(LexError::from(format!(
-                            "Expected '->' but found {}",
-                            self.current()
-                        )));
+                        Err(self.error(format!("Expected '->' but found {}", self.current())))
                     }
                 }
 
                 ',' => {
                     self.step(1);
                     self.complete();
-                    return Ok(Token::Comma);
+                    Ok(Token::Comma)
                 }
 
                 ';' => {
                     self.step(1);
                     self.complete();
-                    return Ok(Token::Semi);
+                    Ok(Token::Semi)
                 }
 
                 '=' => {
                     self.step(1);
                     self.complete();
-                    return Ok(Token::Equal);
+                    Ok(Token::Equal)
                 }
 
                 // Two character lookahead
@@ -172,14 +229,12 @@ impl<'a> Lexer<'a> {
                     if self.lookahead(1) == '/' {
                         self.step(1);
                         while self.index < self.source.len() && self.current() != '\n' {
-                            self.column += 1;
-                            self.index += 1;
+                            self.step(1);
                         }
-                        self.line += 1;
-                        self.column = 0; // TODO: Figure out why this needs to be zero here for the spans to align properly
-                        self.next()
+                        self.next_token()
                     } else {
-                        return Err(LexError::from(format!("Expected '/' but found {}. Note that division is currently not supported.", self.current())));
+                        self.step(1);
+                        Err(self.error(format!("Expected '/' but found {}. Note that division is currently not supported.", self.current())))
                     }
                 }
 
@@ -187,58 +242,64 @@ impl<'a> Lexer<'a> {
                     if self.lookahead(1) == ':' {
                         self.step(2);
                         self.complete();
-                        return Ok(Token::ColonColon);
+                        Ok(Token::ColonColon)
                     } else {
                         self.step(1);
                         self.complete();
-                        return Ok(Token::Colon);
+                        Ok(Token::Colon)
                     }
                 }
 
                 _ => {
                     self.step(1);
-                    self.complete();
-                    return Err(LexError::from(format!(
-                        "Unexpected character '{}'",
-                        current
-                    )));
+                    Err(self.error(format!("Unexpected character '{}'", current)))
                 }
             }
         }
     }
 
-    pub fn lex(&mut self) -> Result<(Vec<Token>, Vec<Span>), LexError> {
+    /// Lexes the entire source. Characters that can't be lexed are reported as errors and skipped,
+    /// so the returned tokens are always usable.
+    pub fn lex(&mut self) -> (Vec<Token>, Vec<Span>, Vec<LexError>) {
         let mut tokens = Vec::new();
-        let mut token = self.next()?;
+        let mut errors = Vec::new();
 
-        while token != Token::EOF {
-            tokens.push(token.clone());
-            token = self.next()?;
+        loop {
+            match self.next_token() {
+                Ok(Token::Eof) => break,
+                Ok(token) => tokens.push(token),
+                Err(error) => errors.push(error),
+            }
         }
 
-        let loc = Location::new()
-            .with_column(self.column)
-            .with_line(self.line);
-        self.spans.push(Span::new().from(loc.clone()).to(loc));
-        tokens.push(Token::EOF);
+        self.start();
+        self.spans.push(self.span());
+        tokens.push(Token::Eof);
 
-        Ok((tokens, self.spans.clone())) // TODO: Find a way to avoid cloning here
+        (tokens, self.spans.clone(), errors) // TODO: Find a way to avoid cloning here
     }
 
     fn start(&mut self) {
-        self.start.set_column(self.column);
-        self.start.set_line(self.line);
+        self.start = self.offset;
     }
 
-    fn end(&mut self) {
-        self.end.set_column(self.column);
-        self.end.set_line(self.line);
+    /// Completes a span, starting from the last call to `start`. Should be called every time a new token is collected.
+    fn complete(&mut self) {
+        self.spans.push(self.span());
     }
 
-    /// Completes a span, using `self.marker` for the `from` location. Should be called every time a new token is collected.
-    fn complete(&mut self) {
-        self.spans
-            .push(Span::new().from(self.start.clone()).to(self.end.clone()));
+    /// Creates an error spanning the characters consumed since the last call to `start`. Unlike
+    /// `complete`, this doesn't record a span, since no token is produced.
+    fn error(&self, reason: String) -> LexError {
+        LexError::new(reason).with_span(self.span())
+    }
+
+    /// The span of the characters consumed since the last call to `start`.
+    fn span(&self) -> Span {
+        Span::new()
+            .start(self.start)
+            .end(self.offset)
+            .with_file(self.file)
     }
 
     fn current(&self) -> char {
@@ -255,23 +316,47 @@ impl<'a> Lexer<'a> {
 
     fn step(&mut self, n: usize) {
         for _ in 0..n {
-            // Mark the end of the current span
-            self.end();
-
-            self.index += 1;
-
             if self.index >= self.source.len() {
                 break;
             }
 
-            self.column += 1;
-            if self.source[self.index] == '\n' {
-                while self.index <= self.source.len() && self.source[self.index] == '\n' {
-                    self.index += 1;
-                    self.line += 1;
-                }
-                self.column = 1;
-            }
+            self.offset += self.source[self.index].len_utf8();
+            self.index += 1;
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lex(source: &str) -> (Vec<Token>, Vec<LexError>) {
+        let source: Vec<char> = source.chars().collect();
+        let (tokens, _, errors) = Lexer::new(&source).lex();
+        (tokens, errors)
+    }
+
+    #[test]
+    fn origin_variables_are_lexed() {
+        let (tokens, errors) = lex("'o 'a_1");
+
+        assert!(errors.is_empty());
+        assert!(
+            matches!(&tokens[..], [Token::OriginVar(o), Token::OriginVar(a), Token::Eof]
+            if o.to_str() == "o" && a.to_str() == "a_1")
+        );
+    }
+
+    #[test]
+    fn trailing_tick_reaches_the_end_of_the_file() {
+        let (tokens, errors) = lex("fn f<'");
+
+        assert!(matches!(tokens.last(), Some(Token::Eof)));
+        assert_eq!(errors.len(), 1);
+        assert_eq!(
+            errors[0].reason,
+            "Expected the name of an origin variable after ', but reached the end of the file"
+        );
+        assert_eq!((errors[0].span.start, errors[0].span.end), (5, 6));
+    }
+}
@@ -0,0 +1,29 @@
+//! The Inlet analyzer, which checks that data only flows between the origins it's allowed to.
+//!
+//! [`analyze_project`] runs every analysis on a project, the same way the `inlet` binary does. To
+//! work with a single file instead, lex it with [`Lexer`], parse it with [`Parser`] and run the
+//! analyses in [`semantics`] over the resulting [`ast::File`].
+
+pub mod ast;
+mod cache;
+pub mod diagnostic;
+mod driver;
+pub mod error;
+pub mod lattice;
+pub mod lexer;
+pub mod lockfile;
+pub mod parser;
+mod scheduler;
+pub mod semantics;
+pub mod source;
+pub mod span;
+pub mod symbol;
+#[cfg(test)]
+mod testing;
+pub mod token;
+pub mod visit;
+
+pub use driver::{analyze_project, normalize_path, AnalysisOptions, AnalysisReport, CrateReport};
+pub use lexer::Lexer;
+pub use parser::Parser;
+pub use visit::Visit;
@@ -0,0 +1,121 @@
+use std::path::{Component, Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+/// The version of the lockfile format we write.
+const VERSION: u32 = 1;
+
+const HEADER: &str = concat!(
+    "# This file is generated by Inlet, and records which sources were analyzed.\n",
+    "# It is not intended for manual editing.\n",
+);
+
+/// Records every crate that was analyzed, so later runs can check that nothing has changed.
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+pub struct Lockfile {
+    pub version: u32,
+    #[serde(default, rename = "crate")]
+    pub crates: Vec<LockedCrate>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct LockedCrate {
+    pub name: String,
+    /// The directory of the crate, relative to the lockfile.
+    pub path: PathBuf,
+    /// A hash of the crate's manifest and every source file that was loaded for it.
+    pub hash: String,
+}
+
+impl Lockfile {
+    /// Creates a lockfile listing `crates`, sorted by name so it's stable between runs.
+    pub fn new(mut crates: Vec<LockedCrate>) -> Self {
+        crates.sort_by(|a, b| a.name.cmp(&b.name));
+
+        Lockfile {
+            version: VERSION,
+            crates,
+        }
+    }
+
+    pub fn parse(source: &str) -> Result<Self, toml::de::Error> {
+        toml::from_str(source)
+    }
+
+    pub fn render(&self) -> String {
+        let body = toml::to_string(self).expect("Lockfiles should always be serializable");
+        format!("{}\n{}", HEADER, body)
+    }
+
+    /// Describes every way `self` differs from `locked`, the lockfile it's expected to match.
+    pub fn differences(&self, locked: &Lockfile) -> Vec<String> {
+        let mut differences = vec![];
+
+        for krate in &self.crates {
+            match locked.crates.iter().find(|c| c.name == krate.name) {
+                None => differences.push(format!(
+                    "Crate '{}' was analyzed, but isn't listed in `Inlet.lock`",
+                    krate.name
+                )),
+                Some(c) if c.path != krate.path => differences.push(format!(
+                    "Crate '{}' was found at '{}', but `Inlet.lock` expects it at '{}'",
+                    krate.name,
+                    krate.path.display(),
+                    c.path.display()
+                )),
+                Some(c) if c.hash != krate.hash => differences.push(format!(
+                    "The sources of crate '{}' have changed since `Inlet.lock` was written",
+                    krate.name
+                )),
+                Some(_) => {}
+            }
+        }
+
+        for krate in &locked.crates {
+            if !self.crates.iter().any(|c| c.name == krate.name) {
+                differences.push(format!(
+                    "Crate '{}' is listed in `Inlet.lock`, but is no longer used",
+                    krate.name
+                ));
+            }
+        }
+
+        differences
+    }
+}
+
+/// Hashes a crate's files, given as paths relative to the crate along with their contents.
+pub fn hash<'a>(files: impl IntoIterator<Item = (PathBuf, &'a str)>) -> String {
+    let mut files: Vec<_> = files.into_iter().collect();
+    files.sort_by(|a, b| a.0.cmp(&b.0));
+
+    // Lengths are included so the boundaries between files can't be shifted around
+    let mut hasher = Sha256::new();
+    for (path, source) in files {
+        let path = path.to_string_lossy();
+        hasher.update((path.len() as u64).to_le_bytes());
+        hasher.update(path.as_bytes());
+        hasher.update((source.len() as u64).to_le_bytes());
+        hasher.update(source.as_bytes());
+    }
+
+    format!("sha256:{:x}", hasher.finalize())
+}
+
+/// Finds a path that leads from the directory `from` to `to`. Both must be normalized.
+pub fn relative(from: &Path, to: &Path) -> PathBuf {
+    let from: Vec<Component> = from.components().collect();
+    let to: Vec<Component> = to.components().collect();
+    let common = from.iter().zip(&to).take_while(|(a, b)| a == b).count();
+
+    let path: PathBuf = std::iter::repeat_n(Component::ParentDir, from.len() - common)
+        .chain(to[common..].iter().copied())
+        .collect();
+
+    if path.as_os_str().is_empty() {
+        PathBuf::from(".")
+    } else {
+        path
+    }
+}
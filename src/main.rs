@@ -1,247 +1,338 @@
 use std::{
-    collections::HashMap,
+    collections::BTreeSet,
     fs,
-    iter::successors,
-    panic,
-    path::{self, Component, Path, PathBuf},
-    sync::Arc,
+    io::IsTerminal,
+    path::{Path, PathBuf},
+    process::ExitCode,
 };
 
-use serde::Deserialize;
+use clap::{Parser as ClapParser, Subcommand, ValueEnum};
 
-use lexer::Lexer;
-use parser::Parser;
-use semantics::{Analysis, FunctionAnalysis, FunctionData, IdentAnalysis, OriginAnalysis};
+use inlet::{
+    analyze_project,
+    diagnostic::{render_json, render_sarif, Diagnostic, Emitter, Severity},
+    lockfile::Lockfile,
+    normalize_path,
+    semantics::SemaErrorKind,
+    source::SourceMap,
+    AnalysisOptions, AnalysisReport, CrateReport,
+};
 
-mod ast;
-mod diagnostic;
-mod error;
-mod lexer;
-mod parser;
-mod semantics;
-mod span;
-mod symbol;
-mod token;
-mod visit;
+/// The exit code when the analysis found at least one error.
+const EXIT_VIOLATIONS: u8 = 1;
 
-use clap::Parser as ClapParser;
-use symbol::SymbolTable;
+/// The exit code when the project couldn't be analyzed at all, like when a manifest is missing.
+const EXIT_FAILED: u8 = 2;
 
 /// The Inlet Compiler.
 #[derive(ClapParser)]
-#[command(version, about, long_about = None)]
+#[command(version, about, long_about = None, subcommand_negates_reqs = true)]
 struct Arguments {
-    /// Specify the root directory of your project.
-    #[arg(short, long)]
-    path: PathBuf,
-}
+    #[command(subcommand)]
+    command: Option<Command>,
 
-#[derive(Deserialize)]
-struct Manifest {
-    package: Package,
-    dependencies: HashMap<String, Dependency>,
+    /// Specify the root directory of your project.
+    #[arg(short, long, required = true)]
+    path: Option<PathBuf>,
+
+    /// Specify when diagnostics should be colored.
+    #[arg(long, value_enum, default_value_t = ColorChoice::Auto)]
+    color: ColorChoice,
+
+    /// Specify how diagnostics should be printed.
+    #[arg(long, value_enum, default_value_t = MessageFormat::Human)]
+    message_format: MessageFormat,
+
+    /// Fail if `Inlet.lock` is missing or doesn't match the sources being analyzed, rather than
+    /// updating it.
+    #[arg(long)]
+    locked: bool,
+
+    /// Print the signature of every function whose return origin is inferred from `{_}`, with
+    /// that origin filled in, after the diagnostics.
+    #[arg(long)]
+    print_inferred: bool,
 }
 
-#[derive(Deserialize)]
-struct Package {
-    name: String,
+#[derive(Subcommand)]
+enum Command {
+    /// Print a detailed explanation of an error code, such as `I0003`.
+    Explain { code: String },
 }
 
-#[derive(Deserialize)]
-struct Dependency {
-    path: PathBuf,
+#[derive(Clone, Copy, ValueEnum)]
+enum MessageFormat {
+    /// Human-readable diagnostics with source snippets.
+    Human,
+    /// One JSON object per diagnostic, on its own line.
+    Json,
+    /// A single SARIF 2.1.0 log containing every diagnostic, printed once analysis is complete.
+    Sarif,
 }
 
-struct ProcessContext {
-    dependencies: HashMap<String, PathBuf>,
-    functions: SymbolTable<FunctionData>,
-    binary_exists: bool,
+#[derive(Clone, Copy, ValueEnum)]
+enum ColorChoice {
+    /// Color diagnostics when printing to a terminal.
+    Auto,
+    Always,
+    Never,
 }
 
-impl ProcessContext {
-    pub fn new() -> Self {
-        ProcessContext {
-            dependencies: HashMap::new(),
-            functions: SymbolTable::new(),
-            binary_exists: false,
+impl ColorChoice {
+    fn enabled(&self) -> bool {
+        match self {
+            Self::Auto => std::io::stdout().is_terminal() && std::env::var_os("NO_COLOR").is_none(),
+            Self::Always => true,
+            Self::Never => false,
         }
     }
 }
 
-fn process_crate(path: &Path, ctx: &mut ProcessContext) {
-    // First, let's read the manifest to see if there are any dependencies we should look at first
-    let manifest = &fs::read_to_string(path.join("Inlet.toml")).expect(&format!(
-        "Could not find `Inlet.toml` in {}. Does it exist?",
-        path.to_str().unwrap()
-    ));
-
-    let manifest: Manifest = toml::from_str(manifest).expect(&format!(
-        "Failed to parse `Inlet.toml` in {}. Is it formatted correctly?",
-        path.to_str().unwrap()
-    ));
+/// Prints diagnostics in the format the user asked for.
+struct Printer {
+    /// Every file that was loaded, so diagnostics can point into any of them.
+    source_map: SourceMap,
+    color: bool,
+    format: MessageFormat,
+    /// The number of errors (not warnings) emitted so far.
+    errors: usize,
+    /// Diagnostics that are printed all at once after analysis, rather than as they're found.
+    deferred: Vec<Diagnostic>,
+    /// Every error code emitted so far, so we can point users towards `inlet explain`.
+    codes: BTreeSet<String>,
+}
 
-    // Next, let's figure out whether this is a binary or library crate
-    let binary = fs::exists(path.join("main.inlet")).unwrap();
-    let library = fs::exists(path.join("lib.inlet")).unwrap();
+impl Printer {
+    pub fn new(source_map: SourceMap) -> Self {
+        Printer {
+            source_map,
+            color: false,
+            format: MessageFormat::Human,
+            errors: 0,
+            deferred: vec![],
+            codes: BTreeSet::new(),
+        }
+    }
 
-    if !binary && !library {
-        panic!("Crate '{}' has neither a 'main.inlet' (binary entrypoint) or 'lib.rs' (library entrypoint).", manifest.package.name);
+    pub fn with_color(mut self, color: bool) -> Self {
+        self.color = color;
+        self
     }
 
-    if ctx.binary_exists && binary {
-        panic!(
-            "Binary crate '{}' cannot serve as a dependency for another binary crate.",
-            manifest.package.name
-        );
+    pub fn with_format(mut self, format: MessageFormat) -> Self {
+        self.format = format;
+        self
     }
 
-    for (dep_name, dep) in manifest.dependencies {
-        if ctx.dependencies.contains_key(&dep_name) {
-            if ctx.dependencies[&dep_name] != dep.path {
-                panic!(
-                    "Dependency '{}' of crate '{}' has already been analyzed at a different path.",
-                    dep_name, manifest.package.name
-                )
-            }
-        } else {
-            // This dependency hasn't been processed yet... let's do it!
-            process_crate(&path.join(&dep.path), ctx);
+    /// Prints a diagnostic to the user.
+    pub fn emit(&mut self, diagnostic: &Diagnostic) {
+        if diagnostic.is_error() {
+            self.errors += 1;
         }
-    }
 
-    // Now we can process this crate! Right now, every crate only has ONE file
-    let file = if binary { "main.inlet" } else { "lib.inlet" };
-    let source = fs::read_to_string(path.join(file)).expect(&format!(
-        "Couldn't find file '{}' in crate '{}'",
-        path.join(file).to_str().unwrap(),
-        manifest.package.name
-    ));
-
-    // We'll begin by lexing the source
-    let slice = source.chars().collect::<Vec<char>>();
-
-    // Next, we'll run the lexer
-    let mut lexer = Lexer::new(&slice);
-    let (tokens, spans) = lexer.lex().unwrap();
-
-    // Then, we'll run the parser
-    let mut parser = Parser::new(&tokens, &spans);
-    let ast = parser.parse_file().unwrap(); // TODO: Figure out a better way to handle errors
-
-    // Next, we'll perform some simple semantic analysis
-    // For starters, let's collect all function definitions and then make sure all identifiers are defined
-    let krate = manifest.package.name.clone();
-    let functions = FunctionAnalysis::new(&ast, krate.clone(), &ctx.functions)
-        .analyze()
-        .expect("TODO: Handle function collection errors properly.");
-
-    ctx.functions = functions;
-
-    let ident_analysis = IdentAnalysis::new(&ast, &ctx.functions).analyze();
-    if let Err(errors) = ident_analysis {
-        // We encountered one or more semantic errors... print them
-        for error in errors {
-            // Find the source line with this error
-            let line = source
-                .split('\n')
-                .nth(error.span.from.line - 1)
-                .expect("Could not locate original source code line. This is a bug!");
-
-            let length = if error.span.to.line > error.span.from.line {
-                line.len() - error.span.from.column // TODO: Test this
-            } else {
-                error.span.to.column - error.span.from.column + 1
-            };
-
-            let marker = " ".repeat(error.span.from.column - 1) + &"~".repeat(length);
-            let col_num_len = (error.span.from.line).to_string().len();
-
-            println!(
-                "[ERROR] [{}]\n{}\n",
-                normalize_path(path).join(file).to_str().unwrap(),
-                error.message
-            );
-            println!("{}:{}", error.span.from.line, line);
-            println!("{} {}\n", " ".repeat(col_num_len), marker);
+        if let Some(code) = &diagnostic.code {
+            self.codes.insert(code.clone());
+        }
+
+        match self.format {
+            MessageFormat::Human => {
+                let emitter = Emitter::new(&self.source_map).with_color(self.color);
+                println!("{}", emitter.render(diagnostic));
+            }
+            MessageFormat::Json => println!("{}", render_json(diagnostic, &self.source_map)),
+            MessageFormat::Sarif => self.deferred.push(diagnostic.clone()),
         }
     }
 
-    // That's out of the way! Now, let's run the origin analysis
-    let origin_analysis = OriginAnalysis::new(&ast, &ctx.functions, krate).analyze();
+    /// Prints the diagnostics of every crate, grouped by the workspace member they belong to.
+    pub fn emit_crates(&mut self, diagnostics: &[Diagnostic], crates: &[CrateReport]) {
+        for diagnostic in diagnostics {
+            self.emit(diagnostic);
+        }
 
-    if let Err(errors) = origin_analysis {
-        // We encountered one or more semantic errors... print them
-        for error in errors {
-            let mut r = error.span.from.line;
-            let mut c = error.span.from.column;
+        let mut member = None;
+        for krate in crates {
+            if krate.member != member {
+                member = krate.member.clone();
 
-            if r > 0 {
-                r -= 1;
+                if let (Some(member), MessageFormat::Human) = (&member, self.format) {
+                    println!("Checking workspace member '{}'\n", member);
+                }
             }
 
-            if c > 0 {
-                c -= 1;
+            for diagnostic in &krate.diagnostics {
+                self.emit(diagnostic);
             }
-
-            // Find the source line with this error
-            let line = source
-                .split('\n')
-                .nth(r)
-                .expect("Could not locate original source code line. This is a bug!");
-
-            let length = if error.span.to.line > error.span.from.line {
-                line.len() - error.span.from.column // TODO: Test this
-            } else {
-                error.span.to.column - error.span.from.column + 1
-            };
-
-            let marker = " ".repeat(c) + &"~".repeat(length);
-            let col_num_len = (error.span.from.line).to_string().len();
-
-            println!(
-                "[ERROR] [{}]\n{}\n",
-                normalize_path(path).join(file).to_str().unwrap(),
-                error.message
-            );
-            println!("{}:{}", error.span.from.line, line);
-            println!("{} {}\n", " ".repeat(col_num_len), marker);
         }
     }
-}
-
-pub fn normalize_path(path: &Path) -> PathBuf {
-    let mut components = path.components().peekable();
-    let mut ret = if let Some(c @ Component::Prefix(..)) = components.peek().cloned() {
-        components.next();
-        PathBuf::from(c.as_os_str())
-    } else {
-        PathBuf::new()
-    };
 
-    for component in components {
-        match component {
-            Component::Prefix(..) => unreachable!(),
-            Component::RootDir => {
-                ret.push(component.as_os_str());
+    /// Prints the signature of every function whose return origin was inferred, along with where
+    /// it's declared, so it can be pasted back in place of `{_}`. Machine-readable formats get
+    /// them as notes, so they don't break the output.
+    pub fn print_inferred(&mut self, crates: &[CrateReport]) {
+        for (path, function) in crates.iter().flat_map(|krate| &krate.inferred) {
+            let name = path.rsplit("::").next().unwrap_or(path);
+
+            if !matches!(self.format, MessageFormat::Human) {
+                let diagnostic =
+                    Diagnostic::new(format!("Inferred signature `{}`", function.signature(name)))
+                        .with_severity(Severity::Note)
+                        .with_span(function.span.clone())
+                        .with_analysis("inference".to_string());
+                self.emit(&diagnostic);
+                continue;
             }
-            Component::CurDir => {}
-            Component::ParentDir => {
-                ret.pop();
+
+            match (
+                function.span.file,
+                self.source_map.locations(&function.span),
+            ) {
+                (Some(file), Some((start, _))) => println!(
+                    "{}:{}:{}: {}",
+                    self.source_map.path(file).display(),
+                    start.line,
+                    start.column,
+                    function.signature(name)
+                ),
+                _ => println!("{}", function.signature(name)),
             }
-            Component::Normal(c) => {
-                ret.push(c);
+        }
+    }
+
+    /// Prints any diagnostics that couldn't be printed as soon as they were emitted.
+    pub fn flush(&mut self) {
+        match self.format {
+            MessageFormat::Human => {
+                if let Some(code) = self.codes.first() {
+                    let codes = self.codes.iter().cloned().collect::<Vec<_>>().join(", ");
+                    println!("Some diagnostics have detailed explanations: {}.", codes);
+                    println!(
+                        "For more information about a diagnostic, try `inlet explain {}`.",
+                        code
+                    );
+                }
             }
+            MessageFormat::Json => {}
+            MessageFormat::Sarif => println!("{}", render_sarif(&self.deferred, &self.source_map)),
         }
+
+        self.deferred.clear();
     }
-    ret
 }
 
-fn main() {
+fn main() -> ExitCode {
     let arguments = Arguments::parse();
-    let root = arguments.path;
 
-    let mut ctx = ProcessContext::new();
-    process_crate(&root, &mut ctx);
+    if let Some(Command::Explain { code }) = arguments.command {
+        return explain(&code);
+    }
+
+    let root = normalize_path(
+        &arguments
+            .path
+            .expect("clap should require a path when no subcommand is given"),
+    );
+
+    let report = analyze_project(&root, AnalysisOptions::new().with_cache(true));
+
+    // A lockfile is only useful if it lists every crate
+    let lockfile = (!report.failed).then(|| report.lockfile());
+
+    let AnalysisReport {
+        diagnostics,
+        crates,
+        source_map,
+        failed,
+        ..
+    } = report;
 
-    return;
+    let mut printer = Printer::new(source_map)
+        .with_color(arguments.color.enabled())
+        .with_format(arguments.message_format);
+    printer.emit_crates(&diagnostics, &crates);
+
+    if arguments.print_inferred {
+        printer.print_inferred(&crates);
+    }
+
+    if let Some(lockfile) = lockfile {
+        lock(&root, lockfile, arguments.locked, &mut printer);
+    }
+
+    printer.flush();
+
+    if failed {
+        ExitCode::from(EXIT_FAILED)
+    } else if printer.errors > 0 {
+        ExitCode::from(EXIT_VIOLATIONS)
+    } else {
+        ExitCode::SUCCESS
+    }
+}
+
+/// Writes `lockfile` to `Inlet.lock` next to the root manifest. When `locked` is set, the existing
+/// lockfile is checked against it instead.
+fn lock(root: &Path, lockfile: Lockfile, locked: bool, printer: &mut Printer) {
+    let path = root.join("Inlet.lock");
+
+    if !locked {
+        if let Err(err) = fs::write(&path, lockfile.render()) {
+            let diagnostic = Diagnostic::new(format!("Couldn't write `Inlet.lock`: {}", err))
+                .with_severity(Severity::Warning)
+                .with_analysis("lockfile".to_owned());
+
+            printer.emit(&diagnostic);
+        }
+
+        return;
+    }
+
+    let Ok(source) = fs::read_to_string(&path) else {
+        // The root is relative to the working directory, and would be empty for `--path .`
+        let diagnostic = Diagnostic::new(format!(
+            "`--locked` was passed, but `{}` doesn't exist",
+            std::path::absolute(&path).unwrap_or(path).display()
+        ))
+        .with_analysis("lockfile".to_owned())
+        .with_help("run without `--locked` once to create it".to_owned());
+
+        return printer.emit(&diagnostic);
+    };
+
+    let file = printer.source_map.add(path, source.clone());
+    let differences = match Lockfile::parse(&source) {
+        Ok(existing) => lockfile.differences(&existing),
+        Err(err) => vec![format!("Couldn't parse `Inlet.lock`: {}", err.message())],
+    };
+
+    for difference in differences {
+        let diagnostic = Diagnostic::new(difference)
+            .with_file(file)
+            .with_analysis("lockfile".to_owned())
+            .with_help(
+                "check that the changes are expected, then run without `--locked` to update `Inlet.lock`"
+                    .to_owned(),
+            );
+
+        printer.emit(&diagnostic);
+    }
+}
+
+/// Prints the long-form explanation of an error code.
+fn explain(code: &str) -> ExitCode {
+    match SemaErrorKind::from_code(code) {
+        Some(kind) => {
+            println!("{}", kind.explanation());
+            ExitCode::SUCCESS
+        }
+        None => {
+            let codes: Vec<&str> = SemaErrorKind::ALL.iter().map(|kind| kind.code()).collect();
+            eprintln!(
+                "'{}' is not a valid error code. Valid codes are: {}",
+                code,
+                codes.join(", ")
+            );
+            ExitCode::from(EXIT_FAILED)
+        }
+    }
 }
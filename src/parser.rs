@@ -1,8 +1,6 @@
-use std::collections::VecDeque;
-
 use crate::ast::{
-    BinExp, Block, Claim, Expr, File, FnCall, FnParam, Ident, Item, ItemFn, ItemMod, Lit, Local,
-    Op, Origin, Path, Return, Stmt,
+    BinExp, Block, Claim, Expr, File, FnCall, FnParam, Ident, Item, ItemFn, ItemMod, ItemUse, Lit,
+    Local, Op, Origin, Path, Return, Stmt, UseTree, UseTreeKind, Visibility,
 };
 
 use crate::error::ParseError;
@@ -14,6 +12,7 @@ pub struct Parser<'a> {
     spans: &'a [Span],
     index: usize,
     starts: Vec<Span>,
+    errors: Vec<ParseError>,
 }
 
 type ParseResult<T> = Result<T, ParseError>;
@@ -25,35 +24,88 @@ impl<'a> Parser<'a> {
             spans,
             index: 0,
             starts: vec![spans[0].clone()],
+            errors: vec![],
+        }
+    }
+
+    /// Parse an entire source file, recovering from as many errors as possible. Items that could
+    /// not be parsed are left out of the returned file, and functions with errors in their body
+    /// are kept with an empty one.
+    pub fn parse(&mut self) -> (File, Vec<ParseError>) {
+        // Start a new span
+        self.start();
+
+        let mut items = self.parse_file().items;
+
+        // `parse_file` stops at closing braces, but there's nothing for them to close at the top level
+        while self.current() == &Token::RBrace {
+            self.errors.push(
+                ParseError::new("Unexpected '}' without a matching '{'".to_owned())
+                    .with_span(self.current_span()),
+            );
+            self.advance(1);
+            items.extend(self.parse_file().items);
         }
+
+        let file = File::new().with_items(items).with_span(self.span());
+        (file, std::mem::take(&mut self.errors))
     }
 
-    /// Parse an entire file
-    pub fn parse_file(&mut self) -> ParseResult<File> {
+    /// Parse a list of items, up until the end of the file or a closing brace
+    pub fn parse_file(&mut self) -> File {
         // Start a new span
         self.start();
 
         let mut items: Vec<Item> = Vec::new();
 
-        while (self.current() != &Token::EOF && self.current() != &Token::RBrace) {
-            items.push(self.parse_item()?);
+        while self.current() != &Token::Eof && self.current() != &Token::RBrace {
+            let depth = self.starts.len();
+            let errors = self.errors.len();
+            let index = self.index;
+
+            match self.parse_item() {
+                // Functions we had to recover inside of are incomplete, but calls to them can still
+                // be checked against their signature
+                Ok(Item::ItemFn(item)) if self.errors.len() > errors => {
+                    let body = Block::new().with_span(item.body.span.clone());
+                    items.push(Item::ItemFn(item.with_body(body).with_recovered(true)));
+                }
+                Ok(item) => items.push(item),
+                Err(error) => {
+                    self.errors.push(error);
+                    self.starts.truncate(depth);
+
+                    // Make sure we don't get stuck on the token that caused the error
+                    if self.index == index {
+                        self.advance(1);
+                    }
+                    self.recover_item();
+                }
+            }
         }
 
-        Ok(File::new().with_items(items).with_span(self.span()))
+        File::new().with_items(items).with_span(self.span())
     }
 
     pub fn parse_item(&mut self) -> ParseResult<Item> {
-        let token = self.current();
+        // Look past the visibility (if there is one) to figure out what kind of item this is
+        let token = match (self.current(), self.lookahead(1)) {
+            (Token::KwPub, Token::LParen) => self.lookahead(4),
+            (Token::KwPub, _) => self.lookahead(1),
+            (token, _) => token,
+        };
 
         if token == &Token::KwFn {
             self.parse_item_fn()
         } else if token == &Token::KwMod {
             self.parse_item_mod()
+        } else if token == &Token::KwUse {
+            self.parse_item_use()
         } else {
-            Err(ParseError::from(format!(
-                "Expected 'fn' or 'mod', found {}",
-                token
-            )))
+            Err(
+                ParseError::from(format!("Expected 'fn', 'mod' or 'use', found '{}'", token))
+                    .with_span(self.current_span()),
+            )
         }
     }
 
@@ -61,12 +113,26 @@ impl<'a> Parser<'a> {
         // Start a new span
         self.start();
 
+        let visibility = self.parse_visibility()?;
+
         // Consume the `fn` token
         self.expect(Token::KwFn)?;
 
         // Read the identifier
         let ident = self.parse_ident()?;
 
+        // Read the origin variables, if there are any
+        let mut origin_params = Vec::new();
+        if self.current() == &Token::Lt {
+            self.advance(1);
+            origin_params.push(self.parse_origin_var()?);
+            while self.current() == &Token::Comma {
+                self.advance(1);
+                origin_params.push(self.parse_origin_var()?);
+            }
+            self.expect(Token::Gt)?;
+        }
+
         // Read the parameters
         self.expect(Token::LParen)?;
         let params = self.parse_param_list()?;
@@ -83,7 +149,9 @@ impl<'a> Parser<'a> {
 
         Ok(Item::ItemFn(
             ItemFn::new()
+                .with_visibility(visibility)
                 .with_ident(ident)
+                .with_origin_params(origin_params)
                 .with_params(params)
                 .with_body(body)
                 .with_ret_origin(ret_origin)
@@ -112,7 +180,19 @@ impl<'a> Parser<'a> {
 
         let ident = self.parse_ident()?;
         self.expect(Token::Colon)?;
+
+        self.start();
         let origin = self.parse_origin()?;
+        let span = self.span();
+
+        // Callers need to know what to pass in without looking at the function's body
+        if matches!(origin, Origin::Inferred) {
+            return Err(ParseError::new(
+                "Only return origins can be inferred, so parameters need an explicit origin like `{*}`"
+                    .to_owned(),
+            )
+            .with_span(span));
+        }
 
         Ok(FnParam::new()
             .with_ident(ident)
@@ -123,31 +203,155 @@ impl<'a> Parser<'a> {
     pub fn parse_origin(&mut self) -> ParseResult<Origin> {
         self.expect(Token::LBrace)?;
 
-        let mut origin = Origin::Universal;
-        if let Token::Ident(ident) = self.current() {
-            let path = self.parse_path()?;
-            origin = Origin::Exact(path);
-        } else {
-            self.expect(Token::Star)?;
+        let origin = self.parse_origin_inner();
+
+        // Skip the rest of a broken origin, so its closing brace isn't mistaken for the end of the
+        // enclosing module
+        if origin.is_err() {
+            self.recover_origin();
+        }
+
+        origin
+    }
+
+    /// Parse the inside of an origin annotation, along with its closing brace.
+    fn parse_origin_inner(&mut self) -> ParseResult<Origin> {
+        // An origin variable stands on its own, since we couldn't tell which alternative of a
+        // union it should be bound to
+        if let Token::OriginVar(_) = self.current() {
+            let ident = self.parse_origin_var()?;
+
+            if self.current() == &Token::Pipe {
+                return Err(ParseError::new(
+                    "Origin variables can't be part of a union".to_owned(),
+                )
+                .with_span(self.current_span()));
+            }
+
+            self.expect(Token::RBrace)?;
+            return Ok(Origin::Variable(ident));
+        }
+
+        // An exclusion like `{!a}` or `{!(a | b)}` accepts values from anywhere but those paths
+        if self.current() == &Token::Bang {
+            self.expect(Token::Bang)?;
+
+            let mut paths = vec![];
+            if self.current() == &Token::LParen {
+                self.expect(Token::LParen)?;
+
+                loop {
+                    paths.push(self.parse_origin_path()?);
+
+                    if self.current() != &Token::Pipe {
+                        break;
+                    }
+
+                    self.expect(Token::Pipe)?;
+                }
+
+                self.expect(Token::RParen)?;
+            } else {
+                paths.push(self.parse_origin_path()?);
+            }
+
+            self.expect(Token::RBrace)?;
+            return Ok(Origin::Exclude(paths));
+        }
+
+        // `{_}` leaves the origin to be inferred from the function's body
+        if matches!(self.current(), Token::Ident(ident) if ident.to_str() == "_")
+            && self.lookahead(1) == &Token::RBrace
+        {
+            self.advance(2);
+            return Ok(Origin::Inferred);
+        }
+
+        // A union like `{a | b}` accepts values from either path
+        let mut paths = vec![];
+        let mut universal = false;
+        loop {
+            if let Token::Ident(_) = self.current() {
+                paths.push(self.parse_origin_path()?);
+            } else if let Token::OriginVar(_) = self.current() {
+                return Err(ParseError::new(
+                    "Origin variables can't be part of a union".to_owned(),
+                )
+                .with_span(self.current_span()));
+            } else if self.current() == &Token::Bang {
+                return Err(ParseError::new(
+                    "Exclusions can't be part of a union, write `{!(a | b)}` to exclude several paths"
+                        .to_owned(),
+                )
+                .with_span(self.current_span()));
+            } else {
+                self.expect(Token::Star)?;
+                universal = true;
+            }
+
+            if self.current() != &Token::Pipe {
+                break;
+            }
+
+            self.expect(Token::Pipe)?;
         }
 
         self.expect(Token::RBrace)?;
+
+        // Every value satisfies `*`, so a union containing it is no different
+        let origin = match paths.len() {
+            _ if universal => Origin::Universal,
+            1 => Origin::Exact(paths.remove(0)),
+            _ => Origin::Union(paths),
+        };
+
         Ok(origin)
     }
 
-    pub fn parse_ident(&mut self) -> ParseResult<Ident> {
+    /// Parse a path inside of an origin, where `_` can't stand for a module.
+    pub fn parse_origin_path(&mut self) -> ParseResult<Path> {
+        let path = self.parse_path()?;
+
+        if let Some(segment) = path.segments.iter().find(|segment| segment.to_str() == "_") {
+            return Err(ParseError::new(
+                "`_` can only be used on its own, as `{_}`, to infer a return origin".to_owned(),
+            )
+            .with_span(segment.span.clone()));
+        }
+
+        Ok(path)
+    }
+
+    pub fn parse_origin_var(&mut self) -> ParseResult<Ident> {
         // Start a new span
         self.start();
 
         match self.current().clone() {
-            Token::Ident(ident) => {
+            Token::OriginVar(ident) => {
                 self.advance(1);
                 Ok(ident.clone().with_span(self.span()))
             }
             _ => Err(ParseError::new(format!(
-                "Expected identifier, found {}",
+                "Expected origin variable, found '{}'",
                 self.current()
-            ))),
+            ))
+            .with_span(self.current_span())),
+        }
+    }
+
+    pub fn parse_ident(&mut self) -> ParseResult<Ident> {
+        // Start a new span
+        self.start();
+
+        match self.current().clone() {
+            Token::Ident(ident) => {
+                self.advance(1);
+                Ok(ident.clone().with_span(self.span()))
+            }
+            _ => Err(
+                ParseError::new(format!("Expected identifier, found '{}'", self.current()))
+                    .with_span(self.current_span()),
+            ),
         }
     }
 
@@ -157,9 +361,32 @@ impl<'a> Parser<'a> {
 
         let mut calls = Vec::new();
 
-        while self.current() != &Token::RBrace {
-            calls.push(self.parse_stmt()?);
-            self.expect(Token::Semi)?;
+        while self.current() != &Token::RBrace && self.current() != &Token::Eof {
+            let depth = self.starts.len();
+
+            match self.parse_stmt() {
+                Ok(stmt) => {
+                    calls.push(stmt);
+
+                    // A missing semicolon doesn't affect the rest of the block, so keep going
+                    if let Err(error) = self.expect(Token::Semi) {
+                        self.errors.push(error);
+
+                        // ...unless there's something else in the way
+                        if !matches!(
+                            self.current(),
+                            Token::KwLet | Token::KwReturn | Token::KwClaim | Token::RBrace
+                        ) {
+                            self.recover_stmt();
+                        }
+                    }
+                }
+                Err(error) => {
+                    self.errors.push(error);
+                    self.starts.truncate(depth);
+                    self.recover_stmt();
+                }
+            }
         }
 
         Ok(Block::new().with_calls(calls).with_span(self.span()))
@@ -176,10 +403,10 @@ impl<'a> Parser<'a> {
             return Ok(Stmt::Claim(self.parse_claim()?));
         }
 
-        Err(ParseError::from(format!(
-            "Unknown statement beginning with '{}'",
-            current
-        )))
+        Err(
+            ParseError::from(format!("Unknown statement beginning with '{}'", current))
+                .with_span(self.current_span()),
+        )
     }
 
     pub fn parse_claim(&mut self) -> ParseResult<Claim> {
@@ -223,11 +450,8 @@ impl<'a> Parser<'a> {
             self.expect(Token::Plus)?;
             let rhs = self.parse_term()?;
             expr = Expr::Bin(
-                BinExp::new(expr, Op::Add, rhs).with_span(
-                    Span::new()
-                        .from(start.from.clone())
-                        .to(self.previous_span().to),
-                ),
+                BinExp::new(expr, Op::Add, rhs)
+                    .with_span(start.clone().end(self.previous_span().end)),
             );
         }
 
@@ -243,11 +467,8 @@ impl<'a> Parser<'a> {
             self.expect(Token::Star)?;
             let rhs = self.parse_factor()?;
             expr = Expr::Bin(
-                BinExp::new(expr, Op::Multiply, rhs).with_span(
-                    Span::new()
-                        .from(start.from.clone())
-                        .to(self.previous_span().to),
-                ),
+                BinExp::new(expr, Op::Multiply, rhs)
+                    .with_span(start.clone().end(self.previous_span().end)),
             );
         }
 
@@ -294,7 +515,8 @@ impl<'a> Parser<'a> {
             _ => Err(ParseError::from(format!(
                 "Expected number or identifier, found '{}'",
                 current
-            ))),
+            ))
+            .with_span(self.current_span())),
         }
     }
 
@@ -333,25 +555,147 @@ impl<'a> Parser<'a> {
         // Start a new span
         self.start();
 
+        let visibility = self.parse_visibility()?;
+
         // Consume the `fn` token
         self.expect(Token::KwMod)?;
 
         // Read the identifier
         let ident = self.parse_ident()?;
 
-        // Read the brackets and function body
+        // A `mod name;` declaration has its body in another file, which is loaded later on
+        if self.current() == &Token::Semi {
+            self.expect(Token::Semi)?;
+
+            return Ok(Item::ItemMod(
+                ItemMod::new()
+                    .with_visibility(visibility)
+                    .with_ident(ident)
+                    .with_inline(false)
+                    .with_span(self.span()),
+            ));
+        }
+
+        // Read the brackets and module body
         self.expect(Token::LBrace)?;
-        let file = self.parse_file()?;
+        let file = self.parse_file();
         self.expect(Token::RBrace)?;
 
         Ok(Item::ItemMod(
             ItemMod::new()
+                .with_visibility(visibility)
                 .with_ident(ident)
                 .with_file(file)
                 .with_span(self.span()),
         ))
     }
 
+    /// Parses an optional `pub` or `pub(crate)`. Items without one are private.
+    pub fn parse_visibility(&mut self) -> ParseResult<Visibility> {
+        if self.current() != &Token::KwPub {
+            return Ok(Visibility::Private);
+        }
+
+        self.expect(Token::KwPub)?;
+
+        if self.current() != &Token::LParen {
+            return Ok(Visibility::Public);
+        }
+
+        self.expect(Token::LParen)?;
+        match self.current() {
+            Token::Ident(ident) if ident.to_str() == "crate" => self.advance(1),
+            token => {
+                return Err(ParseError::from(format!(
+                    "Expected 'crate' inside of `pub(...)`, found '{}'",
+                    token
+                ))
+                .with_span(self.current_span()))
+            }
+        }
+        self.expect(Token::RParen)?;
+
+        Ok(Visibility::Crate)
+    }
+
+    pub fn parse_item_use(&mut self) -> ParseResult<Item> {
+        // Start a new span
+        self.start();
+
+        if self.current() == &Token::KwPub {
+            return Err(ParseError::from("Imports can't be made `pub`".to_owned())
+                .with_span(self.current_span()));
+        }
+
+        // Consume the `use` token
+        self.expect(Token::KwUse)?;
+
+        let tree = self.parse_use_tree()?;
+        self.expect(Token::Semi)?;
+
+        Ok(Item::ItemUse(
+            ItemUse::new().with_tree(tree).with_span(self.span()),
+        ))
+    }
+
+    pub fn parse_use_tree(&mut self) -> ParseResult<UseTree> {
+        // Start a new span
+        self.start();
+
+        // Read the prefix, up until a group like `{a, b}` (if there is one)
+        let mut segments = Vec::new();
+        let mut group = self.current() == &Token::LBrace;
+
+        if !group {
+            segments.push(self.parse_ident()?);
+
+            while self.current() == &Token::ColonColon {
+                self.expect(Token::ColonColon)?;
+
+                if self.current() == &Token::LBrace {
+                    group = true;
+                    break;
+                }
+
+                segments.push(self.parse_ident()?);
+            }
+        }
+
+        let mut prefix = Path::new();
+        if let (Some(first), Some(last)) = (segments.first(), segments.last()) {
+            prefix = prefix.with_span(first.span.clone().end(last.span.end));
+        }
+        let prefix = prefix.with_segments(segments);
+
+        let kind = if group {
+            self.expect(Token::LBrace)?;
+
+            let mut trees = Vec::new();
+            while self.current() != &Token::RBrace {
+                trees.push(self.parse_use_tree()?);
+
+                if self.current() != &Token::Comma {
+                    break;
+                }
+
+                self.expect(Token::Comma)?;
+            }
+
+            self.expect(Token::RBrace)?;
+            UseTreeKind::Nested(trees)
+        } else if self.current() == &Token::KwAs {
+            self.expect(Token::KwAs)?;
+            UseTreeKind::Simple(Some(self.parse_ident()?))
+        } else {
+            UseTreeKind::Simple(None)
+        };
+
+        Ok(UseTree::new()
+            .with_prefix(prefix)
+            .with_kind(kind)
+            .with_span(self.span()))
+    }
+
     fn start(&mut self) {
         let mut span = Span::new();
 
@@ -362,24 +706,73 @@ impl<'a> Parser<'a> {
         self.starts.push(span);
     }
 
+    /// Skips tokens until the beginning of the next item, or the end of the enclosing module.
+    fn recover_item(&mut self) {
+        let mut depth = 0;
+
+        loop {
+            match self.current() {
+                Token::Eof => break,
+                Token::KwFn | Token::KwMod | Token::KwUse | Token::KwPub if depth == 0 => break,
+                Token::LBrace => depth += 1,
+                Token::RBrace if depth == 0 => break,
+                Token::RBrace => depth -= 1,
+                _ => {}
+            }
+
+            self.advance(1);
+        }
+    }
+
+    /// Skips tokens until just past the closing brace of the current origin.
+    fn recover_origin(&mut self) {
+        loop {
+            match self.current() {
+                Token::Eof | Token::LBrace => break,
+                Token::RBrace => {
+                    self.advance(1);
+                    break;
+                }
+                _ => self.advance(1),
+            }
+        }
+    }
+
+    /// Skips tokens until the end of the current statement, or the end of the enclosing block.
+    fn recover_stmt(&mut self) {
+        loop {
+            match self.current() {
+                Token::Eof | Token::RBrace => break,
+                Token::Semi => {
+                    self.advance(1);
+                    break;
+                }
+                _ => self.advance(1),
+            }
+        }
+    }
+
     fn finish(&mut self) {
         self.starts.pop();
     }
 
     fn current(&self) -> &Token {
-        if self.index > self.input.len() {
-            return &Token::EOF;
+        if self.index >= self.input.len() {
+            return &Token::Eof;
         }
 
         &self.input[self.index]
     }
 
+    fn lookahead(&self, n: usize) -> &Token {
+        self.input.get(self.index + n).unwrap_or(&Token::Eof)
+    }
+
     fn current_span(&self) -> Span {
-        if self.index > self.input.len() {
-            return Span::new();
+        match self.spans.get(self.index) {
+            Some(span) => span.clone(),
+            None => self.spans.last().cloned().unwrap_or_else(Span::new),
         }
-
-        self.spans[self.index].clone()
     }
 
     fn span(&mut self) -> Span {
@@ -389,9 +782,7 @@ impl<'a> Parser<'a> {
             end = self.spans[self.index - 1].clone();
         }
 
-        let span = Span::new()
-            .from(self.starts.last().unwrap().from.clone())
-            .to(end.to.clone());
+        let span = self.starts.last().unwrap().clone().end(end.end);
 
         self.finish();
         span
@@ -404,9 +795,7 @@ impl<'a> Parser<'a> {
             end = self.spans[self.index - 1].clone();
         }
 
-        let span = Span::new()
-            .from(self.starts.last().unwrap().from.clone())
-            .to(end.to.clone());
+        let span = self.starts.last().unwrap().clone().end(end.end);
 
         span
     }
@@ -435,12 +824,12 @@ impl<'a> Parser<'a> {
             return Ok(self.current());
         }
 
-        let err_span = self.span();
+        self.finish();
         Err(ParseError::from(format!(
-            "Expected '{}' but found '{}' ({})",
+            "Expected '{}' but found '{}'",
             kind,
-            self.current(),
-            err_span
-        )))
+            self.current()
+        ))
+        .with_span(self.current_span()))
     }
 }
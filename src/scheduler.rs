@@ -0,0 +1,164 @@
+use std::{
+    collections::VecDeque,
+    panic::{self, AssertUnwindSafe},
+    sync::{mpsc, Arc},
+    thread,
+};
+
+/// Work that's waiting for a thread to run on.
+enum Job<A, B> {
+    Prepare(usize),
+    Finish(usize, A, Vec<Arc<B>>),
+}
+
+/// Work that a thread has finished.
+enum Done<A, B> {
+    Prepared(usize, A),
+    Finished(usize, B),
+}
+
+/// Runs two steps for every node in a dependency graph, spread across as many threads as the
+/// machine has. `prepare` runs for every node straight away, since it can't depend on any other
+/// node. `finish` runs for a node once it's been prepared and every node it depends on has
+/// finished, and is given their results.
+///
+/// `dependencies` lists the nodes each node depends on, and must not contain any cycles. The
+/// results of `finish` are returned in the same order as the nodes.
+pub fn run<A, B>(
+    dependencies: &[Vec<usize>],
+    prepare: impl Fn(usize) -> A + Sync,
+    finish: impl Fn(usize, A, Vec<Arc<B>>) -> B + Sync,
+) -> Vec<Arc<B>>
+where
+    A: Send,
+    B: Send + Sync,
+{
+    let workers = thread::available_parallelism().map_or(1, |n| n.get());
+    let nodes = dependencies.len();
+
+    let mut prepared: Vec<Option<A>> = (0..nodes).map(|_| None).collect();
+    let mut finished: Vec<Option<Arc<B>>> = (0..nodes).map(|_| None).collect();
+    let mut started = vec![false; nodes];
+
+    thread::scope(|scope| {
+        let (sender, receiver) = mpsc::channel();
+        let mut queue: VecDeque<Job<A, B>> = (0..nodes).map(Job::Prepare).collect();
+        let mut running = 0;
+
+        loop {
+            while running < workers {
+                let Some(job) = queue.pop_front() else {
+                    break;
+                };
+
+                let sender = sender.clone();
+                let (prepare, finish) = (&prepare, &finish);
+                running += 1;
+
+                scope.spawn(move || {
+                    // Panics are sent back instead, so we don't wait forever for this job
+                    let done = panic::catch_unwind(AssertUnwindSafe(|| match job {
+                        Job::Prepare(node) => Done::Prepared(node, prepare(node)),
+                        Job::Finish(node, input, dependencies) => {
+                            Done::Finished(node, finish(node, input, dependencies))
+                        }
+                    }));
+
+                    let _ = sender.send(done);
+                });
+            }
+
+            if running == 0 {
+                break;
+            }
+
+            match receiver.recv().expect("a job should always report back") {
+                Ok(Done::Prepared(node, output)) => prepared[node] = Some(output),
+                Ok(Done::Finished(node, output)) => finished[node] = Some(Arc::new(output)),
+                Err(payload) => panic::resume_unwind(payload),
+            }
+
+            running -= 1;
+
+            // Nodes that are ready to finish go first, since other nodes might be waiting on them
+            for node in 0..nodes {
+                let ready = prepared[node].is_some()
+                    && dependencies[node]
+                        .iter()
+                        .all(|&dependency| finished[dependency].is_some());
+
+                if !started[node] && ready {
+                    started[node] = true;
+
+                    let input = prepared[node].take().expect("node should be prepared");
+                    let results = dependencies[node]
+                        .iter()
+                        .map(|&dependency| finished[dependency].clone().expect("checked above"))
+                        .collect();
+
+                    queue.push_front(Job::Finish(node, input, results));
+                }
+            }
+        }
+    });
+
+    finished
+        .into_iter()
+        .map(|output| output.expect("every node should finish, since there are no cycles"))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use super::*;
+
+    #[test]
+    fn nodes_finish_after_their_dependencies() {
+        // 3 depends on 1 and 2, which both depend on 0
+        let dependencies = vec![vec![], vec![0], vec![0], vec![1, 2]];
+        let order = Mutex::new(vec![]);
+
+        let results = run(
+            &dependencies,
+            |node| node * 10,
+            |node, prepared, results: Vec<Arc<Vec<usize>>>| {
+                order.lock().unwrap().push(node);
+
+                // Every node lists itself after everything it depends on
+                let mut path: Vec<usize> = results.iter().flat_map(|r| r.iter().copied()).collect();
+                path.push(prepared);
+                path
+            },
+        );
+
+        let order = order.into_inner().unwrap();
+        let position = |node| order.iter().position(|&n| n == node).unwrap();
+        for (node, deps) in dependencies.iter().enumerate() {
+            for &dep in deps {
+                assert!(position(dep) < position(node), "{:?}", order);
+            }
+        }
+
+        let results: Vec<Vec<usize>> = results.iter().map(|r| r.to_vec()).collect();
+        assert_eq!(
+            results,
+            [vec![0], vec![0, 10], vec![0, 20], vec![0, 10, 0, 20, 30]]
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "node 1 failed")]
+    fn panics_are_passed_on() {
+        run(
+            &[vec![], vec![0], vec![1]],
+            |_| (),
+            |node, (), _: Vec<Arc<()>>| {
+                if node == 1 {
+                    panic!("node {} failed", node);
+                }
+            },
+        );
+    }
+}
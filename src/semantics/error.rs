@@ -1,18 +1,220 @@
-use crate::span::Span;
+use crate::{
+    diagnostic::{Diagnostic, Label, Severity},
+    span::Span,
+};
 
 pub type SemaResult<T> = Result<T, Vec<SemaError>>;
 
+/// Every kind of problem the semantic analyses can report.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SemaErrorKind {
+    UndefinedIdentifier,
+    UndefinedFunction,
+    ParamOriginMismatch,
+    ReturnOriginMismatch,
+    ArityMismatch,
+    MissingReturn,
+    UnreachableCode,
+    ModuleNotFound,
+    AmbiguousModule,
+    UnresolvedImport,
+    AmbiguousImport,
+    InvalidRelativePath,
+    PrivateFunction,
+    DuplicateDefinition,
+    DuplicateParameter,
+    DependencyNameMismatch,
+    DuplicatePackage,
+    DependencyCycle,
+    UndefinedOriginVariable,
+    ConflictingOriginVariable,
+}
+
+impl SemaErrorKind {
+    pub const ALL: [SemaErrorKind; 20] = [
+        Self::UndefinedIdentifier,
+        Self::UndefinedFunction,
+        Self::ParamOriginMismatch,
+        Self::ReturnOriginMismatch,
+        Self::ArityMismatch,
+        Self::MissingReturn,
+        Self::UnreachableCode,
+        Self::ModuleNotFound,
+        Self::AmbiguousModule,
+        Self::UnresolvedImport,
+        Self::AmbiguousImport,
+        Self::InvalidRelativePath,
+        Self::PrivateFunction,
+        Self::DuplicateDefinition,
+        Self::DuplicateParameter,
+        Self::DependencyNameMismatch,
+        Self::DuplicatePackage,
+        Self::DependencyCycle,
+        Self::UndefinedOriginVariable,
+        Self::ConflictingOriginVariable,
+    ];
+
+    /// A stable, human-readable identifier for this kind of error. Tools consuming our output
+    /// rely on these, so they must never change.
+    pub fn id(&self) -> &'static str {
+        match self {
+            Self::UndefinedIdentifier => "undefined-identifier",
+            Self::UndefinedFunction => "undefined-function",
+            Self::ArityMismatch => "arity-mismatch",
+            Self::ParamOriginMismatch => "parameter-origin-mismatch",
+            Self::ReturnOriginMismatch => "return-origin-mismatch",
+            Self::MissingReturn => "missing-return",
+            Self::UnreachableCode => "unreachable-code",
+            Self::ModuleNotFound => "module-not-found",
+            Self::AmbiguousModule => "ambiguous-module",
+            Self::UnresolvedImport => "unresolved-import",
+            Self::AmbiguousImport => "ambiguous-import",
+            Self::InvalidRelativePath => "invalid-relative-path",
+            Self::PrivateFunction => "private-function",
+            Self::DuplicateDefinition => "duplicate-definition",
+            Self::DuplicateParameter => "duplicate-parameter",
+            Self::DependencyNameMismatch => "dependency-name-mismatch",
+            Self::DuplicatePackage => "duplicate-package",
+            Self::DependencyCycle => "dependency-cycle",
+            Self::UndefinedOriginVariable => "undefined-origin-variable",
+            Self::ConflictingOriginVariable => "conflicting-origin-variable",
+        }
+    }
+
+    /// A stable error code for this kind of error, which can be passed to `inlet explain`.
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::UndefinedIdentifier => "I0001",
+            Self::UndefinedFunction => "I0002",
+            Self::ParamOriginMismatch => "I0003",
+            Self::ReturnOriginMismatch => "I0004",
+            Self::ArityMismatch => "I0005",
+            Self::MissingReturn => "I0006",
+            Self::UnreachableCode => "I0007",
+            Self::ModuleNotFound => "I0008",
+            Self::AmbiguousModule => "I0009",
+            Self::UnresolvedImport => "I0010",
+            Self::AmbiguousImport => "I0011",
+            Self::InvalidRelativePath => "I0012",
+            Self::PrivateFunction => "I0013",
+            Self::DuplicateDefinition => "I0014",
+            Self::DuplicateParameter => "I0015",
+            Self::DependencyNameMismatch => "I0016",
+            Self::DuplicatePackage => "I0017",
+            Self::DependencyCycle => "I0018",
+            Self::UndefinedOriginVariable => "I0019",
+            Self::ConflictingOriginVariable => "I0020",
+        }
+    }
+
+    /// Finds the kind of error with the given code, ignoring case.
+    pub fn from_code(code: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|kind| kind.code().eq_ignore_ascii_case(code))
+    }
+
+    /// A long-form explanation of this kind of error, along with an example.
+    pub fn explanation(&self) -> &'static str {
+        match self {
+            Self::UndefinedIdentifier => include_str!("explanations/I0001.md"),
+            Self::UndefinedFunction => include_str!("explanations/I0002.md"),
+            Self::ParamOriginMismatch => include_str!("explanations/I0003.md"),
+            Self::ReturnOriginMismatch => include_str!("explanations/I0004.md"),
+            Self::ArityMismatch => include_str!("explanations/I0005.md"),
+            Self::MissingReturn => include_str!("explanations/I0006.md"),
+            Self::UnreachableCode => include_str!("explanations/I0007.md"),
+            Self::ModuleNotFound => include_str!("explanations/I0008.md"),
+            Self::AmbiguousModule => include_str!("explanations/I0009.md"),
+            Self::UnresolvedImport => include_str!("explanations/I0010.md"),
+            Self::AmbiguousImport => include_str!("explanations/I0011.md"),
+            Self::InvalidRelativePath => include_str!("explanations/I0012.md"),
+            Self::PrivateFunction => include_str!("explanations/I0013.md"),
+            Self::DuplicateDefinition => include_str!("explanations/I0014.md"),
+            Self::DuplicateParameter => include_str!("explanations/I0015.md"),
+            Self::DependencyNameMismatch => include_str!("explanations/I0016.md"),
+            Self::DuplicatePackage => include_str!("explanations/I0017.md"),
+            Self::DependencyCycle => include_str!("explanations/I0018.md"),
+            Self::UndefinedOriginVariable => include_str!("explanations/I0019.md"),
+            Self::ConflictingOriginVariable => include_str!("explanations/I0020.md"),
+        }
+    }
+
+    /// A one-line description of this kind of error.
+    pub fn description(&self) -> &'static str {
+        match self {
+            Self::UndefinedIdentifier => "An identifier was used without being defined",
+            Self::UndefinedFunction => "A function was called without being defined",
+            Self::ArityMismatch => "A function was called with the wrong number of arguments",
+            Self::ParamOriginMismatch => {
+                "An argument's origin does not satisfy the origin required by its parameter"
+            }
+            Self::ReturnOriginMismatch => {
+                "A returned value's origin does not satisfy the function's return origin"
+            }
+            Self::MissingReturn => "A function can reach the end of its body without returning",
+            Self::UnreachableCode => "A statement can never be executed",
+            Self::ModuleNotFound => {
+                "A module was declared, but the file it lives in couldn't be found"
+            }
+            Self::AmbiguousModule => {
+                "A module was declared, but more than one file could contain it"
+            }
+            Self::UnresolvedImport => "A `use` declaration imports a path that doesn't exist",
+            Self::AmbiguousImport => {
+                "Two `use` declarations import different paths under the same name"
+            }
+            Self::InvalidRelativePath => {
+                "A path uses `self`, `super` or `crate` somewhere they aren't allowed"
+            }
+            Self::PrivateFunction => {
+                "A function was called from outside of the modules it's visible in"
+            }
+            Self::DuplicateDefinition => {
+                "A function or module was defined more than once in the same module"
+            }
+            Self::DuplicateParameter => "A function has more than one parameter with the same name",
+            Self::DependencyNameMismatch => {
+                "A dependency's name doesn't match the name its crate gives itself"
+            }
+            Self::DuplicatePackage => "A crate depends on another crate under its own name",
+            Self::DependencyCycle => "A crate depends on itself, directly or through other crates",
+            Self::UndefinedOriginVariable => {
+                "An origin variable was used without being declared by its function"
+            }
+            Self::ConflictingOriginVariable => {
+                "An origin variable was bound to two origins that don't satisfy each other"
+            }
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct SemaError {
+    pub kind: SemaErrorKind,
     pub message: String,
     pub span: Span,
+    pub severity: Severity,
+    /// The canonical path of the module this error was found in.
+    pub module: Option<String>,
+    pub labels: Vec<Label>,
+    pub notes: Vec<String>,
+    pub help: Vec<String>,
 }
 
 impl SemaError {
-    pub fn new() -> Self {
+    /// Creates a new error of the given kind. Every error must have a kind, so it's required
+    /// up front rather than through the builder methods.
+    pub fn new(kind: SemaErrorKind) -> Self {
         SemaError {
+            kind,
             message: String::new(),
             span: Span::new(),
+            severity: Severity::Error,
+            module: None,
+            labels: vec![],
+            notes: vec![],
+            help: vec![],
         }
     }
 
@@ -25,4 +227,104 @@ impl SemaError {
         self.span = span;
         self
     }
+
+    pub fn with_severity(mut self, severity: Severity) -> Self {
+        self.severity = severity;
+        self
+    }
+
+    pub fn with_module(mut self, module: String) -> Self {
+        self.module = Some(module);
+        self
+    }
+
+    pub fn with_label(mut self, label: Label) -> Self {
+        self.labels.push(label);
+        self
+    }
+
+    pub fn with_note(mut self, note: String) -> Self {
+        self.notes.push(note);
+        self
+    }
+
+    pub fn with_help(mut self, help: String) -> Self {
+        self.help.push(help);
+        self
+    }
+
+    pub fn into_diagnostic(self) -> Diagnostic {
+        let mut diagnostic = Diagnostic::new(self.message)
+            .with_severity(self.severity)
+            .with_rule(self.kind.id().to_owned())
+            .with_code(self.kind.code().to_owned())
+            .with_span(self.span)
+            .with_labels(self.labels)
+            .with_notes(self.notes);
+
+        for help in self.help {
+            diagnostic = diagnostic.with_help(help);
+        }
+
+        if let Some(module) = self.module {
+            diagnostic = diagnostic.with_module(module);
+        }
+
+        diagnostic
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::BTreeSet, fs};
+
+    use super::*;
+    use crate::{analyze_project, AnalysisOptions};
+
+    /// Explanations whose erroneous example needs more than a single source file, such as other
+    /// crates or manifests, so it can't be checked on its own.
+    const MULTI_FILE: [&str; 5] = ["I0009", "I0013", "I0016", "I0017", "I0018"];
+
+    /// The first code block of an explanation, which is always its erroneous example.
+    fn example(explanation: &str) -> String {
+        explanation
+            .split("```")
+            .nth(1)
+            .expect("every explanation has an example")
+            .trim_start_matches('\n')
+            .to_owned()
+    }
+
+    #[test]
+    fn examples_produce_their_own_error() {
+        let mut mismatches = vec![];
+
+        for kind in SemaErrorKind::ALL {
+            if MULTI_FILE.contains(&kind.code()) {
+                continue;
+            }
+
+            let dir = std::env::temp_dir().join(format!(
+                "inlet-explain-{}-{}",
+                std::process::id(),
+                kind.code()
+            ));
+            fs::create_dir_all(&dir).unwrap();
+            fs::write(dir.join("Inlet.toml"), "[package]\nname = \"app\"\n").unwrap();
+            fs::write(dir.join("main.inlet"), example(kind.explanation())).unwrap();
+
+            let report = analyze_project(&dir, AnalysisOptions::new());
+            let codes: BTreeSet<_> = report
+                .all_diagnostics()
+                .filter_map(|diagnostic| diagnostic.code.as_deref())
+                .collect();
+            fs::remove_dir_all(&dir).unwrap();
+
+            if report.failed || codes != BTreeSet::from([kind.code()]) {
+                mismatches.push(format!("{}: {:?}", kind.code(), codes));
+            }
+        }
+
+        assert!(mismatches.is_empty(), "{}", mismatches.join("\n"));
+    }
 }
@@ -1,44 +1,256 @@
+use serde::{Deserialize, Serialize};
+
 use crate::{
-    ast::{Expr, File, Origin},
+    ast::{File, Ident, Origin, Visibility},
+    diagnostic::Label,
+    span::Span,
     symbol::SymbolTable,
-    visit::{visit_expr, visit_item_fn, visit_item_mod, visit_local, Visit},
+    visit::{visit_item_mod, Visit},
+};
+
+use super::{
+    canonicalize_origin,
+    error::{SemaError, SemaErrorKind},
+    Analysis, ImportData,
 };
 
 /// This analysis finds every function declaration and stores its canonical path.
 pub struct FunctionAnalysis<'a> {
     file: &'a File,
+    /// The names of the crate and its dependencies, which origins can start with.
+    roots: &'a SymbolTable<ImportData>,
     prefix: String,
+    /// The module that every item we're currently visiting is restricted to by the visibility of
+    /// the modules around it. Empty if those modules are visible everywhere.
+    scope: String,
     table: SymbolTable<FunctionData>,
+    /// The span of the name of every module defined in this file, keyed by its canonical path.
+    modules: SymbolTable<Span>,
+    errors: Vec<SemaError>,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct FunctionData {
+    /// The names of the origin variables the function declares, without their leading quotes.
+    pub origin_params: Vec<String>,
     pub params: Vec<ParamData>,
     pub ret_origin: Origin,
+    /// Whether `ret_origin` was inferred from the function's body, because it was written as
+    /// `{_}`.
+    pub inferred: bool,
+    pub visibility: Visibility,
+    /// The module this function can be called from, along with every module inside of it. This
+    /// accounts for the visibility of the modules around the function, and is empty if it can
+    /// be called from anywhere.
+    pub scope: String,
+    /// The span of the function's name.
+    pub span: Span,
+}
+
+impl FunctionData {
+    /// Writes out this function's signature the way it could be declared, with `name` as its name.
+    pub fn signature(&self, name: &str) -> String {
+        let visibility = match self.visibility {
+            Visibility::Private => "",
+            Visibility::Crate => "pub(crate) ",
+            Visibility::Public => "pub ",
+        };
+
+        let origin_params = match self.origin_params.is_empty() {
+            true => String::new(),
+            false => {
+                let variables: Vec<String> = self
+                    .origin_params
+                    .iter()
+                    .map(|variable| format!("'{}", variable))
+                    .collect();
+                format!("<{}>", variables.join(", "))
+            }
+        };
+
+        let params: Vec<String> = self
+            .params
+            .iter()
+            .map(|param| format!("{}: {{{}}}", param.name, param.origin))
+            .collect();
+
+        format!(
+            "{}fn {}{}({}) -> {{{}}}",
+            visibility,
+            name,
+            origin_params,
+            params.join(", "),
+            self.ret_origin
+        )
+    }
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct ParamData {
     pub name: String,
     pub origin: Origin,
+    pub span: Span,
+}
+
+impl Analysis for FunctionAnalysis<'_> {
+    const NAME: &'static str = "function";
+
+    fn analyze(&mut self) -> super::error::SemaResult<()> {
+        self.visit_file(self.file);
+
+        if !self.errors.is_empty() {
+            return Err(self.errors.clone());
+        }
+
+        Ok(())
+    }
 }
 
 impl<'a> FunctionAnalysis<'a> {
     pub fn new(
         file: &'a File,
+        roots: &'a SymbolTable<ImportData>,
         prefix: String,
-        table: &'a SymbolTable<FunctionData>,
     ) -> FunctionAnalysis<'a> {
         FunctionAnalysis {
             file,
+            roots,
             prefix,
-            table: table.clone(), // This is probably way too expensive
+            scope: String::new(),
+            table: SymbolTable::new(),
+            modules: SymbolTable::new(),
+            errors: vec![],
         }
     }
 
-    pub fn analyze(&mut self) -> super::error::SemaResult<SymbolTable<FunctionData>> {
-        self.visit_file(self.file);
-        Ok(self.table.clone())
+    /// Every function found in this file. When a function is defined more than once, only its
+    /// first definition is kept.
+    pub fn functions(self) -> SymbolTable<FunctionData> {
+        self.table
+    }
+
+    /// Reports a function or module that has already been defined at `first`.
+    fn duplicate(&mut self, what: &str, path: &str, ident: &Ident, first: Span) {
+        self.errors.push(
+            SemaError::new(SemaErrorKind::DuplicateDefinition)
+                .with_message(format!("The {} '{}' is defined multiple times", what, path))
+                .with_span(ident.span.clone())
+                .with_module(self.prefix.clone())
+                .with_label(Label::new(
+                    first,
+                    format!("previous definition of '{}' here", ident),
+                ))
+                .with_help(format!(
+                    "rename or remove one of the definitions of '{}'",
+                    ident
+                )),
+        );
+    }
+
+    /// Reports every parameter or origin variable of a function that shares its name with an
+    /// earlier one, along with every origin variable it uses without declaring.
+    fn check_params(&mut self, node: &crate::ast::ItemFn) {
+        let mut params: SymbolTable<Span> = SymbolTable::new();
+
+        for param in &node.params {
+            let name = param.ident.to_string();
+
+            match params.find(&name) {
+                Some(first) => self.errors.push(
+                    SemaError::new(SemaErrorKind::DuplicateParameter)
+                        .with_message(format!(
+                            "Function '{}' has more than one parameter named '{}'",
+                            node.ident, name
+                        ))
+                        .with_span(param.ident.span.clone())
+                        .with_module(self.prefix.clone())
+                        .with_label(Label::new(first, format!("'{}' first declared here", name))),
+                ),
+                None => params.insert(name, param.ident.span.clone()),
+            }
+        }
+
+        let mut variables: SymbolTable<Span> = SymbolTable::new();
+
+        for variable in &node.origin_params {
+            let name = variable.to_string();
+
+            match variables.find(&name) {
+                Some(first) => self.errors.push(
+                    SemaError::new(SemaErrorKind::DuplicateParameter)
+                        .with_message(format!(
+                            "Function '{}' has more than one origin variable named `'{}`",
+                            node.ident, name
+                        ))
+                        .with_span(variable.span.clone())
+                        .with_module(self.prefix.clone())
+                        .with_label(Label::new(
+                            first,
+                            format!("`'{}` first declared here", name),
+                        )),
+                ),
+                None => variables.insert(name, variable.span.clone()),
+            }
+        }
+
+        let origins = node
+            .params
+            .iter()
+            .map(|param| &param.origin)
+            .chain([&node.ret_origin]);
+
+        for origin in origins {
+            let Origin::Variable(variable) = origin else {
+                continue;
+            };
+
+            if variables.find(variable.to_str()).is_none() {
+                self.errors.push(
+                    SemaError::new(SemaErrorKind::UndefinedOriginVariable)
+                        .with_message(format!(
+                            "Function '{}' uses the origin variable `'{}` without declaring it",
+                            node.ident, variable
+                        ))
+                        .with_span(variable.span.clone())
+                        .with_module(self.prefix.clone())
+                        .with_label(Label::new(
+                            node.ident.span.clone(),
+                            "origin variables are declared after the function's name".to_owned(),
+                        ))
+                        .with_help(format!(
+                            "declare it with `fn {}<'{}>(...)`",
+                            node.ident, variable
+                        )),
+                );
+            }
+        }
+    }
+
+    /// Expands a relative origin, so other modules can compare against it. Invalid origins are
+    /// reported by `OriginAnalysis`, so they're kept as they are here.
+    fn origin(&self, origin: &Origin) -> Origin {
+        canonicalize_origin(&self.prefix, self.roots, origin).unwrap_or_else(|_| origin.clone())
+    }
+
+    /// Finds the module an item declared in the current module with `visibility` is restricted to.
+    fn restrict(&self, visibility: Visibility) -> String {
+        let scope = match visibility {
+            Visibility::Private => self.prefix.clone(),
+            Visibility::Crate => self
+                .prefix
+                .split("::")
+                .next()
+                .unwrap_or_default()
+                .to_owned(),
+            Visibility::Public => String::new(),
+        };
+
+        // Every scope is the current module or one of its parents, so the longest one wins
+        if scope.len() > self.scope.len() {
+            scope
+        } else {
+            self.scope.clone()
+        }
     }
 }
 
@@ -47,32 +259,118 @@ impl Visit for FunctionAnalysis<'_> {
         // Construct the canonical path for this function
         let path = self.prefix.clone() + "::" + node.ident.to_str();
 
-        // TODO: Account for functions that are declared multiple times
+        self.check_params(node);
+
+        // The first definition wins, so calls are checked against it
+        if let Some(first) = self.table.find(&path) {
+            self.duplicate("function", &path, &node.ident, first.span);
+            return;
+        }
+
         // TODO: Account for functions declared inside other functions
         self.table.insert(
             path,
             FunctionData {
+                origin_params: node
+                    .origin_params
+                    .iter()
+                    .map(|ident| ident.to_string())
+                    .collect(),
                 params: node
                     .params
                     .iter()
                     .map(|param| ParamData {
                         name: param.ident.to_string(),
-                        origin: param.origin.clone(),
+                        origin: self.origin(&param.origin),
+                        span: param.span.clone(),
                     })
                     .collect(),
-                ret_origin: node.ret_origin.clone(),
+                ret_origin: self.origin(&node.ret_origin),
+                inferred: matches!(node.ret_origin, Origin::Inferred),
+                visibility: node.visibility,
+                scope: self.restrict(node.visibility),
+                span: node.ident.span.clone(),
             },
         );
     }
 
     fn visit_item_mod(&mut self, node: &crate::ast::ItemMod) {
+        let path = self.prefix.clone() + "::" + node.ident.to_str();
+        match self.modules.find(&path) {
+            Some(first) => self.duplicate("module", &path, &node.ident, first),
+            None => self.modules.insert(path, node.ident.span.clone()),
+        }
+
         let _prefix = self.prefix.clone();
+        let _scope = self.scope.clone();
 
+        self.scope = self.restrict(node.visibility);
         self.prefix += "::";
         self.prefix += node.ident.to_str();
 
         visit_item_mod(self, node);
 
         self.prefix = _prefix;
+        self.scope = _scope;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{analyze_project, testing::TempProject, AnalysisOptions};
+
+    #[test]
+    fn duplicates_point_at_the_definition_that_is_kept() {
+        let project = TempProject::new("duplicates")
+            .with_file("Inlet.toml", "[package]\nname = \"app\"\n")
+            .with_file(
+                "main.inlet",
+                "mod auth {
+    pub fn check(user: {crate::api}) -> {*} {
+        return 0;
+    }
+
+    pub fn check(user: {*}) -> {*} {
+        return 0;
+    }
+}
+
+mod auth {}
+
+fn main(a: {*}, a: {*}) -> {*} {
+    return crate::auth::check(0);
+}
+",
+            );
+
+        let report = analyze_project(project.path(), AnalysisOptions::new());
+        let line = |span| report.source_map.locations(span).unwrap().0.line;
+
+        let mut found: Vec<(&str, usize, usize)> = report
+            .all_diagnostics()
+            .map(|diagnostic| {
+                let first = diagnostic
+                    .labels
+                    .first()
+                    .map_or(0, |label| line(&label.span));
+                (
+                    diagnostic.code.as_deref().unwrap(),
+                    line(diagnostic.span.as_ref().unwrap()),
+                    first,
+                )
+            })
+            .collect();
+        found.sort();
+
+        // The call is checked against the first `check`, which only accepts values from `api`
+        assert_eq!(
+            found,
+            [
+                ("I0003", 14, 2),
+                ("I0014", 6, 2),
+                ("I0014", 11, 1),
+                ("I0015", 13, 13),
+            ]
+        );
     }
 }
@@ -1,24 +1,32 @@
 use crate::{
-    ast::{Expr, File},
+    ast::{Expr, File, FnCall, Visibility},
+    diagnostic::Label,
     symbol::SymbolTable,
-    visit::{visit_expr, visit_item_fn, visit_local, Visit},
+    visit::{visit_expr, visit_item_fn, visit_item_mod, visit_local, Visit},
 };
 
-use super::{error::SemaError, Analysis, FunctionData};
+use super::{
+    error::{SemaError, SemaErrorKind},
+    resolve, Analysis, FunctionData, ImportData,
+};
 
 /// This analysis ensures that every identifier is defined before usage.
 pub struct IdentAnalysis<'a> {
     file: &'a File,
     functions: &'a SymbolTable<FunctionData>,
+    imports: &'a SymbolTable<ImportData>,
     table: SymbolTable<()>,
+    prefix: String,
     errors: Vec<SemaError>,
 }
 
 impl Analysis for IdentAnalysis<'_> {
+    const NAME: &'static str = "ident";
+
     fn analyze(&mut self) -> super::error::SemaResult<()> {
         self.visit_file(self.file);
 
-        if self.errors.len() > 0 {
+        if !self.errors.is_empty() {
             return Err(self.errors.clone());
         }
 
@@ -27,17 +35,85 @@ impl Analysis for IdentAnalysis<'_> {
 }
 
 impl<'a> IdentAnalysis<'a> {
-    pub fn new(file: &'a File, functions: &'a SymbolTable<FunctionData>) -> IdentAnalysis<'a> {
+    pub fn new(
+        file: &'a File,
+        functions: &'a SymbolTable<FunctionData>,
+        imports: &'a SymbolTable<ImportData>,
+        prefix: String,
+    ) -> IdentAnalysis<'a> {
         IdentAnalysis {
             file,
             functions,
+            imports,
             table: SymbolTable::new(),
+            prefix,
             errors: vec![],
         }
     }
+
+    /// Whether a function can be called from the current module.
+    fn visible(&self, data: &FunctionData) -> bool {
+        data.scope.is_empty()
+            || self.prefix == data.scope
+            || self.prefix.starts_with(&format!("{}::", data.scope))
+    }
+
+    fn private_error(&self, fun_name: &str, data: &FunctionData, fun_call: &FnCall) -> SemaError {
+        let module = fun_name
+            .rsplit_once("::")
+            .map_or(fun_name, |(module, _)| module);
+        let krate = module.split("::").next().unwrap_or_default();
+
+        // Figure out whether it's the function itself or a module around it that's too private
+        let own_scope = match data.visibility {
+            Visibility::Private => module,
+            Visibility::Crate => krate,
+            Visibility::Public => "",
+        };
+        let help = if own_scope != data.scope {
+            "the function is inside of a private module, so mark the modules around it as `pub` as well".to_owned()
+        } else if self.prefix.split("::").next() == Some(krate) {
+            "mark the function as `pub(crate)` to call it from anywhere in its crate".to_owned()
+        } else {
+            "mark the function as `pub` to call it from other crates".to_owned()
+        };
+
+        // Say where the function can be called from, which is its crate for `pub(crate)`
+        let visible = if data.visibility == Visibility::Private && own_scope == data.scope {
+            format!("in module '{}' is private", module)
+        } else if data.scope == krate {
+            format!("is only visible inside crate '{}'", krate)
+        } else {
+            format!("is only visible inside module '{}'", data.scope)
+        };
+
+        SemaError::new(SemaErrorKind::PrivateFunction)
+            .with_message(format!(
+                "Function '{}' {}, and can't be called from module '{}'",
+                fun_name, visible, self.prefix
+            ))
+            .with_span(fun_call.path.span.clone())
+            .with_module(self.prefix.clone())
+            .with_label(Label::new(
+                data.span.clone(),
+                "function declared here".to_owned(),
+            ))
+            .with_help(help)
+    }
 }
 
 impl Visit for IdentAnalysis<'_> {
+    fn visit_item_mod(&mut self, node: &crate::ast::ItemMod) {
+        let _prefix = self.prefix.clone();
+
+        self.prefix += "::";
+        self.prefix += node.ident.to_str();
+
+        visit_item_mod(self, node);
+
+        self.prefix = _prefix;
+    }
+
     fn visit_item_fn(&mut self, node: &crate::ast::ItemFn) {
         // Add function parameters to the symbol table
         for param in &node.params {
@@ -63,41 +139,61 @@ impl Visit for IdentAnalysis<'_> {
                 // Make sure this identifier has been defined
                 if self.table.find(&rep).is_none() {
                     self.errors.push(
-                        SemaError::new()
+                        SemaError::new(SemaErrorKind::UndefinedIdentifier)
                             .with_message(format!("Couldn't find a definition for '{}'", rep))
-                            .with_span(path.span.clone()),
+                            .with_span(path.span.clone())
+                            .with_module(self.prefix.clone()),
                     );
                 }
             }
 
             Expr::FunCall(fun_call) => {
-                let fun_name = &fun_call.path.to_string();
+                let fun_name = match resolve(self.imports, &self.prefix, &fun_call.path) {
+                    Ok(fun_name) => fun_name,
+                    Err(errors) => {
+                        self.errors.extend(errors);
+                        return visit_expr(self, node);
+                    }
+                };
 
-                match self.functions.find(fun_name) {
-                    Some(data) => {
-                        // Check that the origin of every passed argument matches
+                let found = fun_name.and_then(|name| Some((self.functions.find(&name)?, name)));
+                match found {
+                    Some((data, fun_name)) => {
+                        if !self.visible(&data) {
+                            self.errors
+                                .push(self.private_error(&fun_name, &data, fun_call));
+                        }
+
+                        // Check that the right number of arguments was passed
                         if data.params.len() != fun_call.args.len() {
                             self.errors.push(
-                                SemaError::new()
+                                SemaError::new(SemaErrorKind::ArityMismatch)
                                     .with_message(format!(
                                         "Function '{}' expects {} arguments, but {} were provided",
                                         fun_name,
                                         data.params.len(),
                                         fun_call.args.len()
                                     ))
-                                    .with_span(fun_call.path.span.clone()),
+                                    .with_span(fun_call.path.span.clone())
+                                    .with_module(self.prefix.clone())
+                                    .with_label(Label::new(
+                                        data.span.clone(),
+                                        "function declared here".to_owned(),
+                                    )),
                             );
                         }
                     }
 
                     None => {
                         self.errors.push(
-                            SemaError::new()
+                            SemaError::new(SemaErrorKind::UndefinedFunction)
                                 .with_message(format!(
                                     "Couldn't find a definition for function '{}'",
-                                    fun_name
+                                    fun_call.path
                                 ))
-                                .with_span(fun_call.path.span.clone()),
+                                .with_span(fun_call.path.span.clone())
+                                .with_module(self.prefix.clone())
+                                .with_help("functions must be called by their full path, starting with the name of their crate, or imported with `use`".to_owned()),
                         );
                     }
                 }
@@ -109,3 +205,62 @@ impl Visit for IdentAnalysis<'_> {
         visit_expr(self, node);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::{analyze_project, testing::TempProject, AnalysisOptions};
+
+    /// Calls `std::fs::read_raw`, declared with `visibility` inside of `mod fs`, from another
+    /// crate, and returns the message of the error that's reported.
+    fn private_message(name: &str, modules: &str, visibility: &str) -> String {
+        let project = TempProject::new(name)
+            .with_file(
+                "app/Inlet.toml",
+                "[package]\nname = \"app\"\n\n[dependencies]\nstd = { path = \"../std\" }\n",
+            )
+            .with_file(
+                "app/main.inlet",
+                "fn main() -> {*} {\n    return std::fs::read_raw(1);\n}\n",
+            )
+            .with_file("std/Inlet.toml", "[package]\nname = \"std\"\n")
+            .with_file(
+                "std/lib.inlet",
+                &format!(
+                    "{}mod fs {{\n    {}fn read_raw(path: {{*}}) -> {{*}} {{\n        return 0;\n    }}\n}}\n",
+                    modules, visibility
+                ),
+            );
+
+        let report = analyze_project(&project.path().join("app"), AnalysisOptions::new());
+        let errors: Vec<_> = report.all_diagnostics().collect();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].code.as_deref(), Some("I0013"));
+
+        errors[0].message.clone()
+    }
+
+    #[test]
+    fn private_functions_are_only_visible_in_their_module() {
+        assert_eq!(
+            private_message("private", "pub ", ""),
+            "Function 'std::fs::read_raw' in module 'std::fs' is private, and can't be called from module 'app'"
+        );
+    }
+
+    #[test]
+    fn crate_functions_are_only_visible_in_their_crate() {
+        assert_eq!(
+            private_message("pub-crate", "pub ", "pub(crate) "),
+            "Function 'std::fs::read_raw' is only visible inside crate 'std', and can't be called from module 'app'"
+        );
+    }
+
+    #[test]
+    fn functions_are_only_visible_where_their_modules_are() {
+        // `fs` is private, but it's declared in the root of `std`, so it's visible in all of `std`
+        assert_eq!(
+            private_message("private-module", "", "pub "),
+            "Function 'std::fs::read_raw' is only visible inside crate 'std', and can't be called from module 'app'"
+        );
+    }
+}
@@ -0,0 +1,265 @@
+use crate::{
+    ast::{File, Ident, ItemUse, Path, UseTree, UseTreeKind},
+    diagnostic::Label,
+    span::Span,
+    symbol::SymbolTable,
+    visit::{visit_item_mod, Visit},
+};
+
+use super::{
+    error::{SemaError, SemaErrorKind},
+    resolve, Analysis, FunctionData,
+};
+
+/// This analysis finds every `use` declaration, and maps the names they import to the canonical
+/// paths they refer to.
+pub struct ImportAnalysis<'a> {
+    file: &'a File,
+    functions: &'a SymbolTable<FunctionData>,
+    /// The names of the crate and its dependencies, keyed by the name alone and mapped to the
+    /// identity of the crate they refer to.
+    aliases: &'a SymbolTable<ImportData>,
+    prefix: String,
+    /// Every imported name, keyed by the canonical path of the module it was imported into
+    /// followed by the name itself.
+    table: SymbolTable<ImportData>,
+    errors: Vec<SemaError>,
+}
+
+#[derive(Clone, Debug)]
+pub struct ImportData {
+    /// The canonical path this name refers to.
+    pub path: String,
+    /// The span of the `use` tree that imported this name.
+    pub span: Span,
+}
+
+impl Analysis for ImportAnalysis<'_> {
+    const NAME: &'static str = "import";
+
+    fn analyze(&mut self) -> super::error::SemaResult<()> {
+        self.visit_file(self.file);
+
+        if !self.errors.is_empty() {
+            return Err(self.errors.clone());
+        }
+
+        Ok(())
+    }
+}
+
+impl<'a> ImportAnalysis<'a> {
+    pub fn new(
+        file: &'a File,
+        functions: &'a SymbolTable<FunctionData>,
+        aliases: &'a SymbolTable<ImportData>,
+        prefix: String,
+    ) -> ImportAnalysis<'a> {
+        ImportAnalysis {
+            file,
+            functions,
+            aliases,
+            prefix,
+            table: SymbolTable::new().with_previous(aliases.clone()),
+            errors: vec![],
+        }
+    }
+
+    /// The names imported so far, followed by the names of the crate and its dependencies. Imports
+    /// that couldn't be resolved are left out.
+    pub fn imports(self) -> SymbolTable<ImportData> {
+        self.table
+    }
+
+    fn collect(&mut self, tree: &UseTree, parent: &[Ident]) {
+        let mut segments = parent.to_vec();
+        segments.extend(tree.prefix.segments.iter().cloned());
+
+        let rename = match &tree.kind {
+            UseTreeKind::Simple(rename) => rename,
+            UseTreeKind::Nested(trees) => {
+                for tree in trees {
+                    self.collect(tree, &segments);
+                }
+
+                return;
+            }
+        };
+
+        let Some(name) = rename.as_ref().or(tree.prefix.segments.last()) else {
+            return;
+        };
+
+        let path = Path::new()
+            .with_segments(segments)
+            .with_span(tree.prefix.span.clone());
+        let resolved = match resolve(self.aliases, &self.prefix, &path) {
+            Ok(resolved) => resolved.filter(|resolved| self.exists(resolved)),
+            Err(errors) => {
+                self.errors.extend(errors);
+                return;
+            }
+        };
+
+        let Some(path) = resolved else {
+            self.errors.push(
+                SemaError::new(SemaErrorKind::UnresolvedImport)
+                    .with_message(format!("Couldn't find a function or module named '{}' to import", path))
+                    .with_span(tree.span.clone())
+                    .with_module(self.prefix.clone())
+                    .with_help("imports must use the full path of what they import, starting with the name of its crate, `crate`, `self` or `super`".to_owned()),
+            );
+
+            return;
+        };
+
+        let key = format!("{}::{}", self.prefix, name);
+        match self.table.find(&key) {
+            Some(existing) if existing.path != path => {
+                self.errors.push(
+                    SemaError::new(SemaErrorKind::AmbiguousImport)
+                        .with_message(format!(
+                            "The name '{}' is imported as both '{}' and '{}'",
+                            name, existing.path, path
+                        ))
+                        .with_span(tree.span.clone())
+                        .with_module(self.prefix.clone())
+                        .with_label(Label::new(
+                            existing.span,
+                            format!("'{}' first imported here", name),
+                        ))
+                        .with_help(
+                            "import one of them under another name, like `use a::b as c;`"
+                                .to_owned(),
+                        ),
+                );
+            }
+
+            Some(_) => { /* Importing the same path twice is harmless */ }
+
+            None => self.table.insert(
+                key,
+                ImportData {
+                    path,
+                    span: tree.span.clone(),
+                },
+            ),
+        }
+    }
+
+    /// Whether `path` is the canonical path of a function, or of a module containing one.
+    fn exists(&self, path: &str) -> bool {
+        let module = format!("{}::", path);
+
+        self.functions.find(path).is_some()
+            || self
+                .functions
+                .iter()
+                .any(|(function, _)| function.starts_with(&module))
+    }
+}
+
+impl Visit for ImportAnalysis<'_> {
+    fn visit_item_mod(&mut self, node: &crate::ast::ItemMod) {
+        let _prefix = self.prefix.clone();
+
+        self.prefix += "::";
+        self.prefix += node.ident.to_str();
+
+        visit_item_mod(self, node);
+
+        self.prefix = _prefix;
+    }
+
+    fn visit_item_use(&mut self, node: &ItemUse) {
+        self.collect(&node.tree, &[]);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{analyze_project, testing::TempProject, AnalysisOptions, AnalysisReport};
+
+    fn analyze(name: &str, source: &str) -> AnalysisReport {
+        let project = TempProject::new(name)
+            .with_file("Inlet.toml", "[package]\nname = \"app\"\n")
+            .with_file("main.inlet", source);
+
+        analyze_project(project.path(), AnalysisOptions::new())
+    }
+
+    fn codes(report: &AnalysisReport) -> Vec<&str> {
+        let mut codes: Vec<&str> = report
+            .all_diagnostics()
+            .filter_map(|diagnostic| diagnostic.code.as_deref())
+            .collect();
+        codes.sort();
+        codes
+    }
+
+    #[test]
+    fn calls_through_imports_resolve_to_what_was_imported() {
+        let report = analyze(
+            "imports-resolve",
+            "mod auth {
+    pub fn check(user: {crate}) -> {*} {
+        return 0;
+    }
+
+    pub fn strict(user: {!crate::api}) -> {*} {
+        return 0;
+    }
+}
+
+mod api {
+    use crate::auth::{check, strict as verify};
+    use super::auth;
+
+    fn handle() -> {*} {
+        let a = check(0);
+        let b = auth::check(1);
+        return verify(2);
+    }
+}
+
+fn main() -> {*} {
+    return 0;
+}
+",
+        );
+
+        // Only the renamed import refers to a function that rejects values from `api`
+        assert_eq!(codes(&report), ["I0003"]);
+        let error = report.all_diagnostics().next().unwrap();
+        assert!(error.message.contains("'app::auth::strict'"));
+    }
+
+    #[test]
+    fn imports_that_are_missing_or_clash_are_reported() {
+        let report = analyze(
+            "imports-errors",
+            "mod a {
+    pub fn f() -> {*} {
+        return 0;
+    }
+}
+
+mod b {
+    pub fn f() -> {*} {
+        return 0;
+    }
+}
+
+use crate::a::f;
+use crate::b::f;
+use crate::c::g;
+
+fn main() -> {*} {
+    return f();
+}
+",
+        );
+
+        assert_eq!(codes(&report), ["I0010", "I0011"]);
+    }
+}
@@ -0,0 +1,176 @@
+use crate::{
+    ast::{File, Local, Origin, Path, Stmt},
+    symbol::SymbolTable,
+    visit::{visit_item_fn, visit_item_mod, Visit},
+};
+
+use super::{
+    canonicalize_origin, error::SemaResult, Analysis, ExprVisitor, FunctionData, ImportData,
+    LocalData,
+};
+
+/// This analysis infers the return origin of every function declared with `-> {_}` from the
+/// values it returns. Those functions can call each other, so their bodies are visited again and
+/// again until none of the inferred origins change.
+pub struct InferenceAnalysis<'a> {
+    file: &'a File,
+    functions: SymbolTable<FunctionData>,
+    imports: &'a SymbolTable<ImportData>,
+    locals: SymbolTable<LocalData>,
+    prefix: String,
+    /// The canonical path of every function in this file whose return origin is inferred.
+    inferred: Vec<String>,
+    /// Every origin returned so far by the function we're currently visiting, joined together.
+    returned: Origin,
+    /// Whether any inferred origin grew while visiting the file this time around.
+    changed: bool,
+}
+
+impl<'a> InferenceAnalysis<'a> {
+    pub fn new(
+        file: &'a File,
+        functions: &SymbolTable<FunctionData>,
+        imports: &'a SymbolTable<ImportData>,
+        prefix: String,
+    ) -> InferenceAnalysis<'a> {
+        InferenceAnalysis {
+            file,
+            functions: functions.clone(),
+            imports,
+            locals: SymbolTable::new(),
+            prefix,
+            inferred: vec![],
+            returned: Origin::Inferred,
+            changed: false,
+        }
+    }
+
+    /// Every function in this file whose return origin was inferred, with that origin filled in.
+    pub fn inferred(&self) -> Vec<(String, FunctionData)> {
+        self.inferred
+            .iter()
+            .filter_map(|name| Some((name.clone(), self.functions.find(name)?)))
+            .collect()
+    }
+
+    /// Evaluates the origin of an expression. Any errors are reported by `OriginAnalysis` later
+    /// on, so the expression is assumed to have any origin instead.
+    fn origin(&self, expr: &crate::ast::Expr) -> Origin {
+        ExprVisitor::visit(
+            &self.prefix,
+            &self.functions,
+            self.imports,
+            &self.locals,
+            expr,
+        )
+        .unwrap_or(Origin::Universal)
+    }
+}
+
+impl Analysis for InferenceAnalysis<'_> {
+    const NAME: &'static str = "inference";
+
+    fn analyze(&mut self) -> SemaResult<()> {
+        // Inferred origins only ever grow, and there are only so many origins they can grow into
+        loop {
+            self.changed = false;
+            self.visit_file(self.file);
+
+            if !self.changed {
+                break;
+            }
+        }
+
+        // A function that only ever returns its own result never returns at all
+        for name in &self.inferred {
+            if let Some(mut data) = self.functions.find(name) {
+                if matches!(data.ret_origin, Origin::Inferred) {
+                    data.ret_origin = Origin::Universal;
+                    self.functions.insert(name.clone(), data);
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Visit for InferenceAnalysis<'_> {
+    fn visit_item_mod(&mut self, node: &crate::ast::ItemMod) {
+        let _prefix = self.prefix.clone();
+
+        self.prefix += "::";
+        self.prefix += node.ident.to_str();
+
+        visit_item_mod(self, node);
+
+        self.prefix = _prefix;
+    }
+
+    fn visit_item_fn(&mut self, node: &crate::ast::ItemFn) {
+        if !matches!(node.ret_origin, Origin::Inferred) {
+            return;
+        }
+
+        // Calls are only ever checked against the first definition of a function
+        let path = self.prefix.clone() + "::" + node.ident.to_str();
+        let Some(mut data) = self
+            .functions
+            .find(&path)
+            .filter(|data| data.span == node.ident.span)
+        else {
+            return;
+        };
+
+        if !self.inferred.contains(&path) {
+            self.inferred.push(path.clone());
+        }
+
+        for param in &node.params {
+            let origin = canonicalize_origin(&self.prefix, self.imports, &param.origin)
+                .unwrap_or_else(|_| param.origin.clone());
+            self.locals
+                .insert(param.ident.to_string(), LocalData { origin });
+        }
+
+        self.returned = Origin::Inferred;
+        visit_item_fn(self, node);
+        self.locals.clear();
+
+        // Reaching the end of the body implicitly returns a value with origin `*`
+        if !node
+            .body
+            .stmts
+            .iter()
+            .any(|stmt| matches!(stmt, Stmt::Return(_)))
+        {
+            self.returned = Origin::Universal;
+        }
+
+        let origin = data.ret_origin.join(&self.returned);
+        if origin != data.ret_origin {
+            data.ret_origin = origin;
+            self.functions.insert(path, data);
+            self.changed = true;
+        }
+    }
+
+    fn visit_local(&mut self, node: &Local) {
+        let origin = self.origin(&node.expr);
+        self.locals
+            .insert(node.ident.to_string(), LocalData { origin });
+    }
+
+    fn visit_return(&mut self, node: &crate::ast::Return) {
+        self.returned = self.returned.join(&self.origin(&node.expr));
+    }
+
+    fn visit_claim(&mut self, node: &crate::ast::Claim) {
+        self.locals.insert(
+            node.ident.to_string(),
+            LocalData {
+                origin: Origin::Exact(Path::from(self.prefix.clone())),
+            },
+        )
+    }
+}
@@ -1,15 +1,22 @@
-use std::{marker::PhantomData, sync::Arc};
-
 use error::SemaResult;
 
 mod error;
 mod function;
 mod ident;
+mod imports;
+mod infer;
 mod origin;
+mod resolve;
+mod returns;
 
+pub use error::{SemaError, SemaErrorKind};
 pub use function::*;
 pub use ident::*;
+pub use imports::*;
+pub use infer::*;
 pub use origin::*;
+pub use resolve::*;
+pub use returns::*;
 
 // pub struct SemanticEngine {
 //     analyses: Vec<Arc<dyn Analysis>>,
@@ -40,5 +47,8 @@ pub use origin::*;
 // }
 
 pub trait Analysis {
+    /// A short name for this analysis, used to tell users which analysis reported a diagnostic.
+    const NAME: &'static str;
+
     fn analyze(&mut self) -> SemaResult<()>;
 }
@@ -1,19 +1,23 @@
 use core::panic;
 
 use crate::{
-    ast::{Expr, File, Local, Origin, Path},
+    ast::{Expr, File, Ident, Local, Origin, Path},
+    diagnostic::Label,
+    span::Span,
     symbol::SymbolTable,
-    visit::{self, visit_item_fn, visit_item_mod, visit_local, Visit},
+    visit::{visit_item_fn, visit_item_mod, Visit},
 };
 
 use super::{
-    error::{SemaError, SemaResult},
-    Analysis, FunctionData,
+    canonicalize_origin,
+    error::{SemaError, SemaErrorKind, SemaResult},
+    resolve, Analysis, FunctionData, ImportData,
 };
 
 pub struct OriginAnalysis<'a> {
     file: &'a File,
     functions: &'a SymbolTable<FunctionData>,
+    imports: &'a SymbolTable<ImportData>,
     locals: SymbolTable<LocalData>,
     cur_func: String,
     ret_origin: Origin,
@@ -22,10 +26,16 @@ pub struct OriginAnalysis<'a> {
 }
 
 impl<'a> OriginAnalysis<'a> {
-    pub fn new(file: &'a File, functions: &'a SymbolTable<FunctionData>, prefix: String) -> Self {
+    pub fn new(
+        file: &'a File,
+        functions: &'a SymbolTable<FunctionData>,
+        imports: &'a SymbolTable<ImportData>,
+        prefix: String,
+    ) -> Self {
         OriginAnalysis {
             file,
             functions,
+            imports,
             locals: SymbolTable::new(),
             cur_func: String::new(),
             ret_origin: Origin::Universal,
@@ -35,11 +45,26 @@ impl<'a> OriginAnalysis<'a> {
     }
 }
 
+impl OriginAnalysis<'_> {
+    /// Expands a relative origin like `{self}` into a canonical one.
+    fn origin(&mut self, origin: &Origin) -> Origin {
+        match canonicalize_origin(&self.prefix, self.imports, origin) {
+            Ok(origin) => origin,
+            Err(errors) => {
+                self.errors.extend(errors);
+                origin.clone()
+            }
+        }
+    }
+}
+
 impl Analysis for OriginAnalysis<'_> {
+    const NAME: &'static str = "origin";
+
     fn analyze(&mut self) -> super::error::SemaResult<()> {
         self.visit_file(self.file);
 
-        if self.errors.len() > 0 {
+        if !self.errors.is_empty() {
             Err(self.errors.clone())
         } else {
             Ok(())
@@ -61,16 +86,17 @@ impl Visit for OriginAnalysis<'_> {
 
     fn visit_item_fn(&mut self, node: &crate::ast::ItemFn) {
         for param in &node.params {
-            self.locals.insert(
-                param.ident.to_string(),
-                LocalData {
-                    origin: param.origin.clone(),
-                },
-            )
+            let origin = self.origin(&param.origin);
+            self.locals
+                .insert(param.ident.to_string(), LocalData { origin })
         }
 
         self.cur_func = node.ident.to_string();
-        self.ret_origin = node.ret_origin.clone();
+        self.ret_origin = match &node.ret_origin {
+            // Inferred origins are built from every returned value, so they can't be violated
+            Origin::Inferred => Origin::Universal,
+            origin => self.origin(origin),
+        };
         visit_item_fn(self, node);
 
         self.ret_origin = Origin::Universal;
@@ -78,7 +104,13 @@ impl Visit for OriginAnalysis<'_> {
     }
 
     fn visit_local(&mut self, node: &Local) {
-        let origin = ExprVisitor::visit(&self.prefix, self.functions, &self.locals, &node.expr);
+        let origin = ExprVisitor::visit(
+            &self.prefix,
+            self.functions,
+            self.imports,
+            &self.locals,
+            &node.expr,
+        );
 
         match origin {
             Ok(origin) => {
@@ -91,17 +123,30 @@ impl Visit for OriginAnalysis<'_> {
     }
 
     fn visit_return(&mut self, node: &crate::ast::Return) {
-        let ret_origin = ExprVisitor::visit(&self.prefix, self.functions, &self.locals, &node.expr);
+        let ret_origin = ExprVisitor::visit(
+            &self.prefix,
+            self.functions,
+            self.imports,
+            &self.locals,
+            &node.expr,
+        );
 
         match ret_origin {
             Ok(ret_origin) => {
                 if !ret_origin.satisfies(&self.ret_origin) {
-                    let message = format!("Function '{}' should return a value with origin '{}', but a value with origin '{}' is returned instead", self.cur_func, self.ret_origin, ret_origin);
-                    self.errors.push(
-                        SemaError::new()
-                            .with_message(message)
-                            .with_span(node.span.clone()),
-                    )
+                    let exclusion = exclusion(&ret_origin, &self.ret_origin);
+                    let message = format!("Function '{}' should return a value with origin {}, but a value with origin {} is returned instead{}", self.cur_func, alternatives(&self.ret_origin), quoted(&ret_origin), exclusion.as_ref().map_or("", |(_, note)| note));
+                    let mut error = SemaError::new(SemaErrorKind::ReturnOriginMismatch)
+                        .with_message(message)
+                        .with_span(node.span.clone())
+                        .with_module(self.prefix.clone());
+
+                    if let Some(span) = origin_span(&self.ret_origin) {
+                        error = error
+                            .with_label(Label::new(span, "return origin declared here".to_owned()));
+                    }
+
+                    self.errors.push(error)
                 }
             }
 
@@ -119,14 +164,75 @@ impl Visit for OriginAnalysis<'_> {
     }
 }
 
+/// Lists every origin that satisfies `origin` on its own, like `'auth' or 'admin'` for a union.
+fn alternatives(origin: &Origin) -> String {
+    let Origin::Union(paths) = origin else {
+        return quoted(origin);
+    };
+
+    let mut paths: Vec<String> = paths.iter().map(|path| format!("'{}'", path)).collect();
+    let last = paths.pop().unwrap_or_default();
+
+    format!("{} or {}", paths.join(", "), last)
+}
+
+/// Quotes `origin` for a message. Variables already start with a quote, so they're put in
+/// backticks instead.
+fn quoted(origin: &Origin) -> String {
+    match origin {
+        Origin::Variable(_) => format!("`{}`", origin),
+        _ => format!("'{}'", origin),
+    }
+}
+
+/// The path excluded by `required` that a value with `origin` could come from, along with a note
+/// for the end of a message saying so.
+fn exclusion<'o>(origin: &Origin, required: &'o Origin) -> Option<(&'o Path, String)> {
+    let excluded = required
+        .excluded()
+        .iter()
+        .find(|excluded| !origin.satisfies(&Origin::Exclude(vec![(*excluded).clone()])))?;
+
+    let note = match origin.satisfies(&Origin::Exact(excluded.clone())) {
+        true => format!(", which comes from the excluded '{}'", excluded),
+        false => format!(", which could come from the excluded '{}'", excluded),
+    };
+
+    Some((excluded, note))
+}
+
+/// The span of every path in `origin`, or of its variable, if it has any.
+fn origin_span(origin: &Origin) -> Option<Span> {
+    if let Origin::Variable(ident) = origin {
+        return Some(ident.span.clone());
+    }
+
+    let paths = match origin {
+        Origin::Exclude(paths) => paths,
+        _ => origin.paths(),
+    };
+    let first = paths.iter().min_by_key(|path| path.span.start)?;
+    let last = paths.iter().max_by_key(|path| path.span.end)?;
+
+    Some(first.span.clone().end(last.span.end))
+}
+
+/// The origin an origin variable stands for in a single call, along with the argument it came from.
+#[derive(Clone)]
+struct Binding {
+    origin: Origin,
+    span: Span,
+}
+
 #[derive(Debug, Clone)]
 pub struct LocalData {
-    origin: Origin,
+    pub origin: Origin,
 }
 
 pub struct ExprVisitor<'a> {
     prefix: &'a str,
     functions: &'a SymbolTable<FunctionData>,
+    imports: &'a SymbolTable<ImportData>,
     locals: &'a SymbolTable<LocalData>,
     errors: Vec<SemaError>,
     origin: Origin,
@@ -136,12 +242,14 @@ impl<'a> ExprVisitor<'a> {
     pub fn visit(
         prefix: &'a str,
         functions: &'a SymbolTable<FunctionData>,
+        imports: &'a SymbolTable<ImportData>,
         locals: &'a SymbolTable<LocalData>,
         expr: &Expr,
     ) -> SemaResult<Origin> {
         let mut visitor = ExprVisitor {
             prefix,
             functions,
+            imports,
             locals,
             errors: vec![],
             origin: Origin::Universal,
@@ -149,7 +257,7 @@ impl<'a> ExprVisitor<'a> {
 
         visitor.visit_expr(expr);
 
-        if visitor.errors.len() > 0 {
+        if !visitor.errors.is_empty() {
             Err(visitor.errors)
         } else {
             Ok(visitor.origin)
@@ -157,6 +265,44 @@ impl<'a> ExprVisitor<'a> {
     }
 }
 
+impl ExprVisitor<'_> {
+    /// Binds an origin variable of `function` to the origin of an argument, which was passed in at
+    /// `span`. A variable that's already bound takes on the more general of the two origins, as
+    /// long as one of them satisfies the other.
+    fn bind(
+        &mut self,
+        function: &str,
+        variable: &Ident,
+        origin: Origin,
+        span: Span,
+        bindings: &mut SymbolTable<Binding>,
+    ) {
+        let name = variable.to_str();
+
+        match bindings.find(name) {
+            Some(bound) if origin.satisfies(&bound.origin) => {}
+            Some(bound) if !bound.origin.satisfies(&origin) => self.errors.push(
+                SemaError::new(SemaErrorKind::ConflictingOriginVariable)
+                    .with_message(format!(
+                        "Origin variable `'{}` of function '{}' is bound to both {} and {}, but neither satisfies the other",
+                        variable, function, quoted(&bound.origin), quoted(&origin)
+                    ))
+                    .with_span(span)
+                    .with_module(self.prefix.to_owned())
+                    .with_label(Label::new(
+                        bound.span,
+                        format!("bound to {} here", quoted(&bound.origin)),
+                    ))
+                    .with_help(
+                        "pass values with related origins, or give each parameter its own origin variable"
+                            .to_owned(),
+                    ),
+            ),
+            _ => bindings.insert(name.to_owned(), Binding { origin, span }),
+        }
+    }
+}
+
 impl<'a> Visit for ExprVisitor<'a> {
     fn visit_bin_expr(&mut self, _: &crate::ast::BinExp) {
         // Any binary expression that occurs in origin `o` will have that origin
@@ -170,12 +316,13 @@ impl<'a> Visit for ExprVisitor<'a> {
             self.origin = data.origin;
         } else {
             self.errors.push(
-                SemaError::new()
+                SemaError::new(SemaErrorKind::UndefinedIdentifier)
                     .with_message(format!(
                         "Could not find definition of identifier '{}'",
                         name
                     ))
-                    .with_span(node.span.clone()),
+                    .with_span(node.span.clone())
+                    .with_module(self.prefix.to_owned()),
             )
         }
     }
@@ -190,33 +337,64 @@ impl<'a> Visit for ExprVisitor<'a> {
     }
 
     fn visit_fn_call(&mut self, node: &crate::ast::FnCall) {
-        let name = node.path.to_string();
-
-        if let Some(data) = self.functions.find(&name) {
-            self.origin = data.ret_origin;
+        // Paths that can't be resolved, or functions that don't exist, are reported by
+        // `IdentAnalysis`
+        let Ok(Some(name)) = resolve(self.imports, self.prefix, &node.path) else {
+            self.origin = Origin::Universal;
+            return;
+        };
+        let Some(data) = self.functions.find(&name) else {
+            self.origin = Origin::Universal;
+            return;
+        };
 
-            for (param, arg) in data.params.iter().zip(node.args.iter()) {
-                let actual = ExprVisitor::visit(&self.prefix, self.functions, &self.locals, arg);
+        // Every origin variable stands for the most general origin that's passed in for it
+        let mut bindings: SymbolTable<Binding> = SymbolTable::new();
 
-                match actual {
-                    Ok(origin) => {
-                        if !origin.satisfies(&param.origin) {
-                            self.errors.push(SemaError::new().with_message(format!("Parameter '{}' of function '{}' must have an origin of '{}', but a value with origin '{}' was provided", param.name, node.path.to_string(), param.origin, origin)).with_span(arg.span()))
-                        }
-                    }
+        for (param, arg) in data.params.iter().zip(node.args.iter()) {
+            let actual =
+                ExprVisitor::visit(self.prefix, self.functions, self.imports, self.locals, arg);
 
-                    Err(errs) => self.errors.extend(errs),
+            let origin = match actual {
+                Ok(origin) => origin,
+                Err(errs) => {
+                    self.errors.extend(errs);
+                    continue;
                 }
+            };
+
+            if let Origin::Variable(variable) = &param.origin {
+                self.bind(&name, variable, origin, arg.span(), &mut bindings);
+            } else if !origin.satisfies(&param.origin) {
+                let exclusion = exclusion(&origin, &param.origin);
+                let message = format!("Parameter '{}' of function '{}' must have an origin of {}, but a value with origin {} was provided{}", param.name, name, alternatives(&param.origin), quoted(&origin), exclusion.as_ref().map_or("", |(_, note)| note));
+
+                let mut error = SemaError::new(SemaErrorKind::ParamOriginMismatch)
+                    .with_message(message)
+                    .with_span(arg.span())
+                    .with_module(self.prefix.to_owned())
+                    .with_label(Label::new(
+                        param.span.clone(),
+                        "parameter declared with origin here".to_owned(),
+                    ));
+
+                if let Some((excluded, _)) = exclusion {
+                    error = error.with_label(Label::new(
+                        excluded.span.clone(),
+                        format!("'{}' excluded here", excluded),
+                    ));
+                }
+
+                self.errors.push(error)
             }
-        } else {
-            self.errors.push(
-                SemaError::new()
-                    .with_message(format!(
-                        "Could not find definition of identifier '{}'",
-                        name
-                    ))
-                    .with_span(node.span.clone()),
-            )
         }
+
+        // A variable that no argument was passed in for could stand for any origin
+        self.origin = match &data.ret_origin {
+            Origin::Variable(variable) => bindings
+                .find(variable.to_str())
+                .map_or(Origin::Universal, |binding| binding.origin),
+            origin => origin.clone(),
+        };
     }
 }
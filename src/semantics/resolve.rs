@@ -0,0 +1,167 @@
+use crate::{
+    ast::{Ident, Origin, Path},
+    lattice,
+    symbol::SymbolTable,
+};
+
+use super::{
+    error::{SemaError, SemaErrorKind, SemaResult},
+    ImportData,
+};
+
+/// Path segments that refer to a module relative to the one they're written in.
+const RELATIVE: [&str; 3] = ["self", "super", "crate"];
+
+/// Resolves a path written inside of `module` to a canonical path. Paths starting with `self`,
+/// `super` or `crate` are expanded, and paths starting with an imported name, the crate's own
+/// name or the name of a dependency have that name replaced with the path it refers to. Returns
+/// `None` if the path starts with anything else, like a crate that's only depended on indirectly
+/// or under another name.
+pub fn resolve(
+    imports: &SymbolTable<ImportData>,
+    module: &str,
+    path: &Path,
+) -> SemaResult<Option<String>> {
+    let canonical = canonicalize(module, path)?;
+
+    let Some((first, rest)) = path.segments.split_first() else {
+        return Ok(Some(canonical.to_string()));
+    };
+
+    if RELATIVE.contains(&first.to_str()) {
+        return Ok(Some(canonical.to_string()));
+    }
+
+    // Crate names are visible from every module, unless an import shadows them
+    let import = imports
+        .find(&format!("{}::{}", module, first))
+        .or_else(|| imports.find(first.to_str()));
+
+    Ok(import.map(|import| {
+        std::iter::once(import.path)
+            .chain(rest.iter().map(|segment| segment.to_string()))
+            .collect::<Vec<String>>()
+            .join("::")
+    }))
+}
+
+/// Expands a path starting with `self`, `super` or `crate` into a canonical path, relative to
+/// `module`. Any other path is returned as it is.
+pub fn canonicalize(module: &str, path: &Path) -> SemaResult<Path> {
+    let mut base: Vec<&str> = module.split("::").collect();
+    let mut segments = path.segments.iter().peekable();
+    let mut relative = false;
+
+    match segments.peek().map(|segment| segment.to_str()) {
+        Some("crate") => {
+            base.truncate(1);
+            segments.next();
+            relative = true;
+        }
+        Some("self") => {
+            segments.next();
+            relative = true;
+        }
+        _ => {}
+    }
+
+    while segments
+        .next_if(|segment| segment.to_str() == "super")
+        .is_some()
+    {
+        if base.len() <= 1 {
+            return Err(vec![SemaError::new(SemaErrorKind::InvalidRelativePath)
+                .with_message(format!(
+                    "Path '{}' refers to the parent of crate '{}', which doesn't exist",
+                    path, base[0]
+                ))
+                .with_span(path.span.clone())
+                .with_module(module.to_owned())]);
+        }
+
+        base.pop();
+        relative = true;
+    }
+
+    let rest: Vec<Ident> = segments.cloned().collect();
+    if let Some(segment) = rest.iter().find(|s| RELATIVE.contains(&s.to_str())) {
+        return Err(vec![SemaError::new(SemaErrorKind::InvalidRelativePath)
+            .with_message(format!(
+                "'{}' can only appear at the start of a path, but it was found in '{}'",
+                segment, path
+            ))
+            .with_span(segment.span.clone())
+            .with_module(module.to_owned())
+            .with_help(
+                "paths can start with `crate`, `self` or `super`, followed by more `super`s"
+                    .to_owned(),
+            )]);
+    }
+
+    if !relative {
+        return Ok(path.clone());
+    }
+
+    let segments = base
+        .into_iter()
+        .map(|segment| Ident::new().with_raw(segment.to_owned()))
+        .chain(rest)
+        .collect();
+
+    Ok(Path::new()
+        .with_segments(segments)
+        .with_span(path.span.clone()))
+}
+
+/// Expands a relative origin like `{self}` or `{super::auth}` into a canonical one. Origins
+/// starting with the name of the crate or one of its dependencies, as given in `roots`, start
+/// with that crate's identity instead. Any other origin is kept as it is.
+pub fn canonicalize_origin(
+    module: &str,
+    roots: &SymbolTable<ImportData>,
+    origin: &Origin,
+) -> SemaResult<Origin> {
+    let canonicalize = |path: &Path| -> SemaResult<Path> {
+        let mut canonical = canonicalize(module, path)?;
+
+        let relative = path
+            .segments
+            .first()
+            .is_some_and(|first| RELATIVE.contains(&first.to_str()));
+        if let (false, Some(first)) = (relative, canonical.segments.first_mut()) {
+            if let Some(root) = roots.find(first.to_str()) {
+                *first = first.clone().with_raw(root.path);
+            }
+        }
+
+        Ok(canonical)
+    };
+
+    match origin {
+        Origin::Universal => Ok(Origin::Universal),
+        Origin::Inferred => Ok(Origin::Inferred),
+        // Variables are replaced by whatever is passed in, which is canonical already
+        Origin::Variable(_) => Ok(origin.clone()),
+        Origin::Exact(path) => canonicalize(path).map(Origin::Exact),
+        Origin::Union(paths) | Origin::Exclude(paths) => {
+            let mut canonical = vec![];
+            let mut errors = vec![];
+
+            for path in paths {
+                match canonicalize(path) {
+                    Ok(path) => canonical.push(path),
+                    Err(errs) => errors.extend(errs),
+                }
+            }
+
+            if !errors.is_empty() {
+                return Err(errors);
+            }
+
+            match origin {
+                Origin::Exclude(_) => Ok(lattice::exclude(canonical)),
+                _ => Ok(lattice::union(canonical.into_iter().map(Origin::Exact))),
+            }
+        }
+    }
+}
@@ -0,0 +1,120 @@
+use crate::{
+    ast::{File, ItemFn, Origin, Stmt},
+    diagnostic::Severity,
+    visit::{visit_item_mod, Visit},
+};
+
+use super::{
+    error::{SemaError, SemaErrorKind},
+    Analysis,
+};
+
+/// This analysis ensures that every function returns a value before reaching the end of its body.
+pub struct ReturnAnalysis<'a> {
+    file: &'a File,
+    prefix: String,
+    errors: Vec<SemaError>,
+}
+
+impl<'a> ReturnAnalysis<'a> {
+    pub fn new(file: &'a File, prefix: String) -> ReturnAnalysis<'a> {
+        ReturnAnalysis {
+            file,
+            prefix,
+            errors: vec![],
+        }
+    }
+}
+
+impl Analysis for ReturnAnalysis<'_> {
+    const NAME: &'static str = "return";
+
+    fn analyze(&mut self) -> super::error::SemaResult<()> {
+        self.visit_file(self.file);
+
+        if !self.errors.is_empty() {
+            return Err(self.errors.clone());
+        }
+
+        Ok(())
+    }
+}
+
+impl Visit for ReturnAnalysis<'_> {
+    fn visit_item_mod(&mut self, node: &crate::ast::ItemMod) {
+        let _prefix = self.prefix.clone();
+
+        self.prefix += "::";
+        self.prefix += node.ident.to_str();
+
+        visit_item_mod(self, node);
+
+        self.prefix = _prefix;
+    }
+
+    fn visit_item_fn(&mut self, node: &ItemFn) {
+        // We don't know how the rest of the body would have gone
+        if node.recovered {
+            return;
+        }
+
+        let stmts = &node.body.stmts;
+
+        // Blocks don't branch (yet), so the only path through a function is its list of statements
+        let ret = stmts
+            .iter()
+            .position(|stmt| matches!(stmt, Stmt::Return(_)));
+
+        match ret {
+            Some(index) => {
+                // Anything after the first `return` can never be executed
+                let unreachable = &stmts[index + 1..];
+
+                if let (Some(first), Some(last)) = (unreachable.first(), unreachable.last()) {
+                    self.errors.push(
+                        SemaError::new(SemaErrorKind::UnreachableCode)
+                            .with_message(format!(
+                                "Function '{}' has unreachable statements after its return statement",
+                                node.ident
+                            ))
+                            .with_span(first.span().end(last.span().end))
+                            .with_severity(Severity::Warning)
+                            .with_module(self.prefix.clone())
+                            .with_help("remove these statements, or move them before the return statement".to_owned()),
+                    );
+                }
+            }
+
+            None => {
+                // Point at the closing brace of the function
+                let span = node.span.clone().start(node.span.end.saturating_sub(1));
+
+                // An inferred origin becomes `*` when the function doesn't return anything
+                if matches!(node.ret_origin, Origin::Universal | Origin::Inferred) {
+                    self.errors.push(
+                        SemaError::new(SemaErrorKind::MissingReturn)
+                            .with_message(format!(
+                                "Function '{}' reaches the end of its body without returning, so it implicitly returns a value with origin '*'",
+                                node.ident
+                            ))
+                            .with_span(span)
+                            .with_severity(Severity::Warning)
+                            .with_module(self.prefix.clone())
+                            .with_help("add a return statement at the end of the function".to_owned()),
+                    );
+                } else {
+                    self.errors.push(
+                        SemaError::new(SemaErrorKind::MissingReturn)
+                            .with_message(format!(
+                                "Function '{}' should return a value with origin '{}', but it can reach the end of its body without returning",
+                                node.ident, node.ret_origin
+                            ))
+                            .with_span(span)
+                            .with_module(self.prefix.clone())
+                            .with_help("add a return statement at the end of the function".to_owned()),
+                    );
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,152 @@
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::span::Span;
+
+/// Identifies a file that has been loaded into a `SourceMap`.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Serialize, Deserialize)]
+pub struct FileId(usize);
+
+impl FileId {
+    /// Creates an ID from its position in some list of files. It only refers to a file in a
+    /// `SourceMap` if that's where the position came from.
+    pub fn from_index(index: usize) -> Self {
+        FileId(index)
+    }
+
+    pub fn index(self) -> usize {
+        self.0
+    }
+}
+
+/// Owns the source text of every file we've loaded, so spans can be turned back into lines and
+/// columns when rendering diagnostics.
+#[derive(Debug)]
+pub struct SourceMap {
+    files: Vec<SourceFile>,
+}
+
+impl SourceMap {
+    pub fn new() -> Self {
+        SourceMap { files: vec![] }
+    }
+
+    pub fn add(&mut self, path: PathBuf, source: String) -> FileId {
+        self.files.push(SourceFile::new(path, source));
+        FileId(self.files.len() - 1)
+    }
+
+    pub fn get(&self, file: FileId) -> &SourceFile {
+        &self.files[file.0]
+    }
+
+    pub fn path(&self, file: FileId) -> &Path {
+        &self.get(file).path
+    }
+
+    /// Finds a file that has already been loaded from `path`.
+    pub fn find(&self, path: &Path) -> Option<FileId> {
+        self.files
+            .iter()
+            .position(|file| file.path == path)
+            .map(FileId)
+    }
+
+    /// Finds the locations of the first and last character of a span, if it points into a file.
+    pub fn locations(&self, span: &Span) -> Option<(Location, Location)> {
+        let file = self.get(span.file?);
+        Some((file.location(span.start), file.location(file.last(span))))
+    }
+}
+
+impl Default for SourceMap {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Debug)]
+pub struct SourceFile {
+    pub path: PathBuf,
+    pub source: String,
+    /// The byte offset of the start of every line.
+    lines: Vec<usize>,
+}
+
+impl SourceFile {
+    fn new(path: PathBuf, source: String) -> Self {
+        let lines = std::iter::once(0)
+            .chain(source.match_indices('\n').map(|(i, _)| i + 1))
+            .collect();
+
+        SourceFile {
+            path,
+            source,
+            lines,
+        }
+    }
+
+    /// Finds the line and column of a byte offset. Both are 1-based, and columns count characters
+    /// rather than bytes.
+    pub fn location(&self, offset: usize) -> Location {
+        let mut offset = offset.min(self.source.len());
+        while !self.source.is_char_boundary(offset) {
+            offset -= 1;
+        }
+
+        let line = self.lines.partition_point(|&start| start <= offset) - 1;
+        let column = self.source[self.lines[line]..offset].chars().count() + 1;
+
+        Location::new().with_line(line + 1).with_column(column)
+    }
+
+    /// The text of a 1-based line, without its line ending.
+    pub fn line(&self, line: usize) -> Option<&str> {
+        let start = *self.lines.get(line.checked_sub(1)?)?;
+        let end = match self.lines.get(line) {
+            Some(next) => next - 1,
+            None => self.source.len(),
+        };
+
+        Some(self.source[start..end].trim_end_matches('\r'))
+    }
+
+    /// The byte offset of the last character in a span. Empty spans point at their start.
+    fn last(&self, span: &Span) -> usize {
+        let end = span.end.min(self.source.len());
+
+        match self.source.get(..end).and_then(|s| s.chars().next_back()) {
+            Some(c) if end > span.start => end - c.len_utf8(),
+            _ => span.start,
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct Location {
+    pub line: usize,
+    pub column: usize,
+}
+
+impl Location {
+    pub fn new() -> Self {
+        Location { line: 0, column: 0 }
+    }
+
+    pub fn with_line(mut self, line: usize) -> Self {
+        self.line = line;
+        self
+    }
+
+    pub fn with_column(mut self, column: usize) -> Self {
+        self.column = column;
+        self
+    }
+}
+
+impl Default for Location {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -1,65 +1,52 @@
 use std::fmt::Display;
 
-#[derive(Clone, PartialEq, Debug)]
+use serde::{Deserialize, Serialize};
+
+use crate::source::FileId;
+
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
 pub struct Span {
-    pub from: Location,
-    pub to: Location,
+    /// The file this span points into, if it came from one.
+    pub file: Option<FileId>,
+    /// The byte offset of the first character in this span.
+    pub start: usize,
+    /// The byte offset just past the last character in this span.
+    pub end: usize,
 }
 
 impl Span {
     pub fn new() -> Self {
         Span {
-            from: Location::new(),
-            to: Location::new(),
+            file: None,
+            start: 0,
+            end: 0,
         }
     }
 
-    pub fn from(mut self, from: Location) -> Self {
-        self.from = from;
+    pub fn start(mut self, start: usize) -> Self {
+        self.start = start;
         self
     }
 
-    pub fn to(mut self, to: Location) -> Self {
-        self.to = to;
+    pub fn end(mut self, end: usize) -> Self {
+        self.end = end;
         self
     }
-}
 
-impl Display for Span {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        f.write_fmt(format_args!(
-            "{}:{} .. {}:{}",
-            self.from.line, self.from.column, self.to.line, self.to.column
-        ))
+    pub fn with_file(mut self, file: Option<FileId>) -> Self {
+        self.file = file;
+        self
     }
 }
 
-#[derive(Clone, PartialEq, Debug)]
-pub struct Location {
-    pub line: usize,
-    pub column: usize,
-}
-
-impl Location {
-    pub fn new() -> Self {
-        Location { line: 0, column: 0 }
-    }
-
-    pub fn set_line(&mut self, line: usize) {
-        self.line = line;
-    }
-
-    pub fn set_column(&mut self, column: usize) {
-        self.column = column;
-    }
-
-    pub fn with_line(mut self, line: usize) -> Self {
-        self.line = line;
-        self
+impl Default for Span {
+    fn default() -> Self {
+        Self::new()
     }
+}
 
-    pub fn with_column(mut self, column: usize) -> Self {
-        self.column = column;
-        self
+impl Display for Span {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_fmt(format_args!("{}..{}", self.start, self.end))
     }
 }
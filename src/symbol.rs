@@ -1,21 +1,23 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, sync::Arc};
 
 #[derive(Debug, Clone)]
 pub struct SymbolTable<T: Clone> {
-    previous: Option<Box<SymbolTable<T>>>,
+    /// Tables that are searched when a symbol isn't found in this one, in order. They're shared,
+    /// so the same table can sit behind many others without being copied.
+    previous: Vec<Arc<SymbolTable<T>>>,
     symbols: HashMap<String, T>,
 }
 
 impl<T: Clone> SymbolTable<T> {
     pub fn new() -> Self {
         SymbolTable {
-            previous: None,
+            previous: vec![],
             symbols: HashMap::new(),
         }
     }
 
-    pub fn with_previous(mut self, previous: SymbolTable<T>) -> Self {
-        self.previous = Some(Box::new(previous));
+    pub fn with_previous(mut self, previous: impl Into<Arc<SymbolTable<T>>>) -> Self {
+        self.previous.push(previous.into());
         self
     }
 
@@ -23,14 +25,26 @@ impl<T: Clone> SymbolTable<T> {
         self.symbols
             .get(symbol)
             .cloned()
-            .or_else(|| self.previous.as_ref().and_then(|prev| prev.find(symbol)))
+            .or_else(|| self.previous.iter().find_map(|prev| prev.find(symbol)))
     }
 
     pub fn insert(&mut self, symbol: String, value: T) {
         self.symbols.insert(symbol, value);
     }
 
+    /// Iterates over every symbol in this table, followed by the symbols of the tables before it.
+    pub fn iter(&self) -> Box<dyn Iterator<Item = (&String, &T)> + '_> {
+        let previous = self.previous.iter().flat_map(|previous| previous.iter());
+        Box::new(self.symbols.iter().chain(previous))
+    }
+
     pub fn clear(&mut self) {
         self.symbols.clear();
     }
 }
+
+impl<T: Clone> Default for SymbolTable<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
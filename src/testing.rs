@@ -0,0 +1,45 @@
+//! Helpers for tests that need a project on disk.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// A directory in the system's temporary directory that's deleted once it's dropped.
+pub struct TempProject {
+    root: PathBuf,
+}
+
+impl TempProject {
+    /// Creates an empty directory. `name` must be unique across tests, since they run in parallel.
+    pub fn new(name: &str) -> Self {
+        let root = std::env::temp_dir().join(format!("inlet-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(&root).expect("temporary directory should be writable");
+
+        TempProject { root }
+    }
+
+    /// Writes `source` to `path`, relative to the project, creating its directory if needed.
+    pub fn with_file(self, path: &str, source: &str) -> Self {
+        self.write(path, source);
+        self
+    }
+
+    pub fn write(&self, path: &str, source: &str) {
+        let path = self.root.join(path);
+        fs::create_dir_all(path.parent().expect("files are inside of the project"))
+            .expect("temporary directory should be writable");
+        fs::write(path, source).expect("temporary directory should be writable");
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.root
+    }
+}
+
+impl Drop for TempProject {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.root);
+    }
+}
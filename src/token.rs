@@ -1,5 +1,3 @@
-use std::fmt::write;
-
 use crate::ast::{Ident, NumLit};
 
 #[derive(Clone, PartialEq, Debug)]
@@ -10,10 +8,15 @@ pub enum Token {
     KwLet,
     KwReturn,
     KwClaim,
+    KwUse,
+    KwAs,
+    KwPub,
 
     // Constructs
     Ident(Ident),
     NumLit(NumLit),
+    /// An origin variable like `'o`, without the leading quote.
+    OriginVar(Ident),
 
     // Delimiters
     Comma,
@@ -31,10 +34,14 @@ pub enum Token {
     Equal,
     Star,
     Plus,
+    Pipe,
+    Bang,
+    Lt,
+    Gt,
     RArrow,
 
     // Other
-    EOF,
+    Eof,
 }
 
 impl std::fmt::Display for Token {
@@ -45,10 +52,15 @@ impl std::fmt::Display for Token {
             Self::KwLet => write!(f, "let"),
             Self::KwReturn => write!(f, "return"),
             Self::KwClaim => write!(f, "claim"),
+            Self::KwUse => write!(f, "use"),
+            Self::KwAs => write!(f, "as"),
+            Self::KwPub => write!(f, "pub"),
 
             Self::Ident(ident) => write!(f, "{}", ident.to_str()),
             Self::NumLit(lit) => write!(f, "{:?}", lit.value()),
+            Self::OriginVar(ident) => write!(f, "'{}", ident.to_str()),
 
+            Self::Comma => write!(f, ","),
             Self::Colon => write!(f, ":"),
             Self::ColonColon => write!(f, "::"),
             Self::Semi => write!(f, ";"),
@@ -62,11 +74,13 @@ impl std::fmt::Display for Token {
             Self::Equal => write!(f, "="),
             Self::Star => write!(f, "*"),
             Self::Plus => write!(f, "+"),
+            Self::Pipe => write!(f, "|"),
+            Self::Bang => write!(f, "!"),
+            Self::Lt => write!(f, "<"),
+            Self::Gt => write!(f, ">"),
             Self::RArrow => write!(f, "->"),
 
-            Self::EOF => write!(f, "EOF"),
-
-            _ => write!(f, "<UNKNOWN>"),
+            Self::Eof => write!(f, "EOF"),
         }
     }
 }
@@ -1,6 +1,6 @@
 use crate::ast::{
-    BinExp, Block, Claim, Expr, File, FnCall, Ident, Item, ItemFn, ItemMod, Lit, Local, NumLit,
-    Origin, Path, Return, Stmt, Unit,
+    BinExp, Block, Claim, Expr, File, FnCall, Ident, Item, ItemFn, ItemMod, ItemUse, Lit, Local,
+    NumLit, Origin, Path, Return, Stmt, Unit,
 };
 
 pub trait Visit: Sized {
@@ -20,6 +20,10 @@ pub trait Visit: Sized {
         visit_item_mod(self, node)
     }
 
+    fn visit_item_use(&mut self, _node: &ItemUse) {
+        // Nothing to do here...
+    }
+
     fn visit_block(&mut self, node: &Block) {
         visit_block(self, node)
     }
@@ -87,6 +91,7 @@ pub fn visit_item(visitor: &mut impl Visit, node: &Item) {
     match node {
         Item::ItemFn(item) => visitor.visit_item_fn(item),
         Item::ItemMod(item) => visitor.visit_item_mod(item),
+        Item::ItemUse(item) => visitor.visit_item_use(item),
     }
 }
 